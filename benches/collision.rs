@@ -0,0 +1,67 @@
+use bevy::{prelude::*, sprite::collide_aabb::collide};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use space_invaders::{collision::spatial_hash::SpatialHash, SPRITE_SCALE};
+
+/// `laserRed01.png` of the sprite sheet
+const LASER_SIZE: Vec2 = Vec2::new(9., 54.);
+
+/// `count` laser-sized boxes spread so density stays about the same at any count.
+fn boxes(count: usize) -> Vec<(Entity, Vec2, Vec2)> {
+    let mut rng = StdRng::seed_from_u64(42);
+    let half_span = (count as f32).sqrt() * 20.;
+    let size = LASER_SIZE * SPRITE_SCALE;
+
+    (0..count)
+        .map(|i| {
+            let center = Vec2::new(
+                rng.gen_range(-half_span..half_span),
+                rng.gen_range(-half_span..half_span),
+            );
+            (Entity::from_raw(i as u32), center, size)
+        })
+        .collect()
+}
+
+fn brute_force(boxes: &[(Entity, Vec2, Vec2)]) -> usize {
+    let mut pairs = 0;
+    for (i, (_, a_center, a_size)) in boxes.iter().enumerate() {
+        for (_, b_center, b_size) in boxes.iter().skip(i + 1) {
+            if collide(a_center.extend(0.), *a_size, b_center.extend(0.), *b_size).is_some() {
+                pairs += 1;
+            }
+        }
+    }
+    pairs
+}
+
+fn spatial_hash(hash: &mut SpatialHash, boxes: &[(Entity, Vec2, Vec2)]) -> usize {
+    hash.clear();
+    for (entity, center, size) in boxes {
+        hash.insert(*entity, *center, *size);
+    }
+    hash.overlapping_pairs().len()
+}
+
+fn bench_collision(c: &mut Criterion) {
+    let mut group = c.benchmark_group("collision");
+    group.sample_size(10);
+
+    for count in [1_000, 10_000] {
+        let boxes = boxes(count);
+        let mut hash = SpatialHash::default();
+        assert_eq!(brute_force(&boxes), spatial_hash(&mut hash, &boxes));
+
+        group.bench_with_input(BenchmarkId::new("brute_force", count), &boxes, |b, boxes| {
+            b.iter(|| brute_force(boxes))
+        });
+        group.bench_with_input(BenchmarkId::new("spatial_hash", count), &boxes, |b, boxes| {
+            b.iter(|| spatial_hash(&mut hash, boxes))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_collision);
+criterion_main!(benches);
@@ -0,0 +1,252 @@
+use bevy::prelude::*;
+
+use crate::TimeScale;
+
+/// How a clip goes on past its last frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlayMode {
+    /// stop on the last frame and do the clip's `OnFinish`
+    Once,
+    /// back to the first frame
+    Loop,
+    /// back and forth between the first and last frames
+    PingPong,
+}
+
+/// What a `PlayMode::Once` clip does after its last frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OnFinish {
+    /// hold the last frame
+    Stop,
+    Despawn,
+    /// play another clip of the animation, by index
+    Play(usize),
+    /// send an `AnimationFinished` event and hold the last frame
+    Notify,
+}
+
+/// Frames of a texture atlas played at a fixed rate
+#[derive(Clone, Debug)]
+pub struct AnimationClip {
+    /// atlas indices, in play order
+    pub frames: Vec<usize>,
+    /// frames per second
+    pub fps: f32,
+    pub mode: PlayMode,
+    pub on_finish: OnFinish,
+}
+
+impl AnimationClip {
+    /// Play `frames` once and stop, e.g. `AnimationClip::new(0..16, 20.)`.
+    pub fn new(frames: impl IntoIterator<Item = usize>, fps: f32) -> Self {
+        Self {
+            frames: frames.into_iter().collect(),
+            fps,
+            mode: PlayMode::Once,
+            on_finish: OnFinish::Stop,
+        }
+    }
+
+    pub fn with_mode(mut self, mode: PlayMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn then(mut self, on_finish: OnFinish) -> Self {
+        self.on_finish = on_finish;
+        self
+    }
+}
+
+/// Event - a clip ending with `OnFinish::Notify` played its last frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AnimationFinished {
+    pub entity: Entity,
+    /// index of the clip in the animation
+    pub clip: usize,
+}
+
+/// Component - plays clips on the entity's `TextureAtlasSprite`, the first
+/// clip from the start
+#[derive(Component, Clone, Debug)]
+pub struct SpriteAnimation {
+    clips: Vec<AnimationClip>,
+    clip: usize,
+    /// position in the clip's frames
+    frame: usize,
+    /// ping-pong direction
+    backwards: bool,
+    /// seconds into the current frame
+    elapsed: f32,
+    finished: bool,
+}
+
+impl SpriteAnimation {
+    pub fn new(clip: AnimationClip) -> Self {
+        Self {
+            clips: vec![clip],
+            clip: 0,
+            frame: 0,
+            backwards: false,
+            elapsed: 0.,
+            finished: false,
+        }
+    }
+
+    /// Add a clip, for `play` and `OnFinish::Play`, at the next index.
+    pub fn with_clip(mut self, clip: AnimationClip) -> Self {
+        self.clips.push(clip);
+        self
+    }
+
+    /// Play clip `clip` from its first frame, ignored if there is no such clip.
+    pub fn play(&mut self, clip: usize) {
+        if clip < self.clips.len() {
+            self.clip = clip;
+            self.frame = 0;
+            self.backwards = false;
+            self.elapsed = 0.;
+            self.finished = false;
+        }
+    }
+
+    /// Index of the playing clip.
+    pub fn clip(&self) -> usize {
+        self.clip
+    }
+
+    /// Atlas index of the frame to show.
+    pub fn index(&self) -> usize {
+        let frames = &self.clips[self.clip].frames;
+        frames
+            .get(self.frame.min(frames.len().saturating_sub(1)))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// A `PlayMode::Once` clip is past its last frame.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Move on by `seconds`, returns what to do if the clip finished.
+    pub fn advance(&mut self, seconds: f32) -> Option<OnFinish> {
+        let clip = &self.clips[self.clip];
+        if self.finished || clip.fps <= 0. || clip.frames.is_empty() {
+            return None;
+        }
+
+        self.elapsed += seconds;
+        let frame_time = 1. / clip.fps;
+        let last = clip.frames.len() - 1;
+        while self.elapsed >= frame_time {
+            self.elapsed -= frame_time;
+            match clip.mode {
+                PlayMode::Once if self.frame == last => {
+                    self.finished = true;
+                    return Some(clip.on_finish);
+                }
+                PlayMode::Once => self.frame += 1,
+                PlayMode::Loop => self.frame = (self.frame + 1) % clip.frames.len(),
+                PlayMode::PingPong if last == 0 => {}
+                PlayMode::PingPong => {
+                    if self.frame == last {
+                        self.backwards = true;
+                    } else if self.frame == 0 {
+                        self.backwards = false;
+                    }
+                    if self.backwards {
+                        self.frame -= 1;
+                    } else {
+                        self.frame += 1;
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Plugin - `SpriteAnimation` playback, at the game's `TimeScale`
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TimeScale>()
+            .add_event::<AnimationFinished>()
+            .add_system(sprite_animation_system);
+    }
+}
+
+fn sprite_animation_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mut finished_events: EventWriter<AnimationFinished>,
+    mut query: Query<(Entity, &mut SpriteAnimation, &mut TextureAtlasSprite)>,
+) {
+    let seconds = time.delta_seconds() * time_scale.0.max(0.);
+
+    for (entity, mut animation, mut sprite) in query.iter_mut() {
+        match animation.advance(seconds) {
+            Some(OnFinish::Despawn) => {
+                commands.entity(entity).despawn_recursive();
+                continue;
+            }
+            Some(OnFinish::Play(clip)) => animation.play(clip),
+            Some(OnFinish::Notify) => finished_events.send(AnimationFinished {
+                entity,
+                clip: animation.clip(),
+            }),
+            Some(OnFinish::Stop) | None => {}
+        }
+        if sprite.index != animation.index() {
+            sprite.index = animation.index();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(animation: &mut SpriteAnimation, steps: usize) -> Vec<usize> {
+        (0..steps)
+            .map(|_| {
+                animation.advance(0.1);
+                animation.index()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn loops_and_ping_pongs() {
+        let mut looped =
+            SpriteAnimation::new(AnimationClip::new(3..6, 10.).with_mode(PlayMode::Loop));
+        assert_eq!(looped.index(), 3);
+        assert_eq!(frames(&mut looped, 5), vec![4, 5, 3, 4, 5]);
+
+        let clip = AnimationClip::new([7, 2, 9], 10.).with_mode(PlayMode::PingPong);
+        let mut ping_pong = SpriteAnimation::new(clip);
+        assert_eq!(frames(&mut ping_pong, 6), vec![2, 9, 2, 7, 2, 9]);
+        assert!(!ping_pong.is_finished());
+    }
+
+    #[test]
+    fn once_finishes_with_its_action() {
+        let mut animation =
+            SpriteAnimation::new(AnimationClip::new(0..3, 10.).then(OnFinish::Play(1)))
+                .with_clip(AnimationClip::new(10..12, 10.).then(OnFinish::Despawn));
+
+        assert_eq!(animation.advance(0.25), None);
+        assert_eq!(animation.index(), 2);
+        assert_eq!(animation.advance(0.1), Some(OnFinish::Play(1)));
+        assert!(animation.is_finished());
+
+        animation.play(1);
+        assert_eq!((animation.clip(), animation.index()), (1, 10));
+        assert_eq!(animation.advance(0.1), None);
+        assert_eq!(animation.advance(0.1), Some(OnFinish::Despawn));
+        assert_eq!(animation.index(), 11);
+    }
+}
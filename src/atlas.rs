@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+
+use bevy::{
+    asset::{AssetLoader, AssetPath, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    sprite::Rect,
+    utils::BoxedFuture,
+};
+
+/// Label of the `TextureAtlas` sub-asset of a loaded `SpriteSheet`
+pub const ATLAS_LABEL: &str = "atlas";
+
+/// A sprite of a `SpriteSheet`, in sheet pixels
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SheetSprite {
+    /// texture index in the sheet's `TextureAtlas`
+    pub index: usize,
+    pub rect: Rect,
+}
+
+impl SheetSprite {
+    pub fn size(&self) -> Vec2 {
+        self.rect.size()
+    }
+}
+
+/// Asset - a Kenney style sprite sheet: one image, with its sprites found by
+/// name through a `sheet.xml` listing
+#[derive(Debug, TypeUuid)]
+#[uuid = "e4ad3bf0-e23d-4c42-b77e-eece16fbc8d8"]
+pub struct SpriteSheet {
+    pub image: Handle<Image>,
+    /// every sprite, in listing order
+    pub atlas: Handle<TextureAtlas>,
+    sprites: HashMap<String, SheetSprite>,
+}
+
+impl SpriteSheet {
+    /// Sheet of `listing`, whose atlas has the listing's sprites in order.
+    pub fn new(listing: &SheetListing, image: Handle<Image>, atlas: Handle<TextureAtlas>) -> Self {
+        let sprites = listing
+            .sprites
+            .iter()
+            .enumerate()
+            .map(|(index, (name, rect))| {
+                let rect = *rect;
+                (name.clone(), SheetSprite { index, rect })
+            })
+            .collect();
+        Self {
+            image,
+            atlas,
+            sprites,
+        }
+    }
+
+    /// The sprite called `name`, e.g. `"playerShip1_blue.png"`.
+    pub fn get(&self, name: &str) -> Result<SheetSprite, String> {
+        self.sprites
+            .get(name)
+            .copied()
+            .ok_or_else(|| format!("no sprite {name} in the sprite sheet"))
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.sprites.keys().map(String::as_str)
+    }
+}
+
+/// Contents of a `sheet.xml`:
+///
+/// ```xml
+/// <TextureAtlas imagePath="sheet.png">
+///     <SubTexture name="beam0.png" x="143" y="377" width="43" height="31"/>
+/// </TextureAtlas>
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SheetListing {
+    /// relative to the listing
+    pub image_path: String,
+    pub sprites: Vec<(String, Rect)>,
+}
+
+impl SheetListing {
+    /// Only the `TextureAtlas` and `SubTexture` elements are read, anything
+    /// else in the file is skipped.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut listing = Self::default();
+        let mut has_atlas = false;
+
+        for tag in tags(text) {
+            let element = tag.split_whitespace().next().unwrap_or_default();
+            match element {
+                "TextureAtlas" => {
+                    listing.image_path = attribute(tag, "imagePath")?.to_string();
+                    has_atlas = true;
+                }
+                "SubTexture" => {
+                    let name = attribute(tag, "name")?;
+                    let number = |key| {
+                        let value = attribute(tag, key)?;
+                        value
+                            .parse::<f32>()
+                            .map_err(|_| format!("bad {key} of sprite {name}: {value}"))
+                    };
+                    let min = Vec2::new(number("x")?, number("y")?);
+                    let size = Vec2::new(number("width")?, number("height")?);
+                    listing.sprites.push((
+                        name.to_string(),
+                        Rect {
+                            min,
+                            max: min + size,
+                        },
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        if !has_atlas {
+            return Err("no TextureAtlas element".to_string());
+        }
+        Ok(listing)
+    }
+}
+
+/// The insides of every `<...>` tag, without the brackets.
+fn tags(text: &str) -> impl Iterator<Item = &str> {
+    text.split('<').skip(1).filter_map(|rest| {
+        rest.split_once('>')
+            .map(|(tag, _)| tag.trim_end_matches('/'))
+    })
+}
+
+fn attribute<'a>(tag: &'a str, key: &str) -> Result<&'a str, String> {
+    let element = tag.split_whitespace().next().unwrap_or_default();
+    tag.split_once(&format!(" {key}=\""))
+        .and_then(|(_, rest)| rest.split_once('"'))
+        .map(|(value, _)| value)
+        .ok_or_else(|| format!("{element} without {key}"))
+}
+
+/// Width and height from the header of a PNG.
+pub fn png_size(bytes: &[u8]) -> Result<Vec2, String> {
+    const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+    if bytes.len() < 24 || &bytes[..8] != SIGNATURE || &bytes[12..16] != b"IHDR" {
+        return Err("not a PNG".to_string());
+    }
+    let u32_at = |i: usize| u32::from_be_bytes(bytes[i..i + 4].try_into().unwrap());
+    Ok(Vec2::new(u32_at(16) as f32, u32_at(20) as f32))
+}
+
+/// Loads `*.xml` sprite sheet listings as `SpriteSheet`s, with the atlas as
+/// the `ATLAS_LABEL` sub-asset.
+#[derive(Default)]
+pub struct SpriteSheetLoader;
+
+impl AssetLoader for SpriteSheetLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let path = load_context.path().display().to_string();
+            let text = std::str::from_utf8(bytes)?;
+            let listing = SheetListing::parse(text).map_err(|err| anyhow(&path, err))?;
+
+            // the atlas needs the image size up front, before the image loads
+            let image_path = load_context
+                .path()
+                .parent()
+                .unwrap_or_else(|| "".as_ref())
+                .join(&listing.image_path);
+            let image_bytes = load_context.read_asset_bytes(&image_path).await?;
+            let image_size = png_size(&image_bytes)
+                .map_err(|err| anyhow(&path, format!("{}: {err}", image_path.display())))?;
+            let image: Handle<Image> =
+                load_context.get_handle(AssetPath::new_ref(&image_path, None));
+
+            let mut atlas = TextureAtlas::new_empty(image.clone(), image_size);
+            for (name, rect) in listing.sprites.iter() {
+                if rect.max.x > image_size.x || rect.max.y > image_size.y {
+                    return Err(anyhow(&path, format!("sprite {name} is outside the image")));
+                }
+                atlas.add_texture(*rect);
+            }
+            let atlas = load_context.set_labeled_asset(
+                ATLAS_LABEL,
+                LoadedAsset::new(atlas).with_dependency(image_path.clone().into()),
+            );
+
+            let sheet = SpriteSheet::new(&listing, image, atlas);
+            load_context
+                .set_default_asset(LoadedAsset::new(sheet).with_dependency(image_path.into()));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["xml"]
+    }
+}
+
+fn anyhow(path: &str, err: String) -> bevy::asset::Error {
+    bevy::asset::Error::msg(format!("{path}: {err}"))
+}
+
+/// Plugin - the `SpriteSheet` asset and its loader
+pub struct SpriteSheetPlugin;
+
+impl Plugin for SpriteSheetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<SpriteSheet>()
+            .init_asset_loader::<SpriteSheetLoader>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHEET: &str = r#"<TextureAtlas imagePath="sheet.png">
+	<SubTexture name="beam0.png" x="143" y="377" width="43" height="31"/>
+	<!-- a comment -->
+	<SubTexture name="ufoRed.png" x="444" y="0" width="91" height="91"/>
+</TextureAtlas>"#;
+
+    #[test]
+    fn parses_a_listing() {
+        let listing = SheetListing::parse(SHEET).unwrap();
+
+        assert_eq!(listing.image_path, "sheet.png");
+        assert_eq!(listing.sprites.len(), 2);
+        let (name, rect) = &listing.sprites[1];
+        assert_eq!(name, "ufoRed.png");
+        assert_eq!(
+            (rect.min, rect.max),
+            (Vec2::new(444., 0.), Vec2::new(535., 91.))
+        );
+    }
+
+    #[test]
+    fn looks_sprites_up_by_name() {
+        let listing = SheetListing::parse(SHEET).unwrap();
+        let sheet = SpriteSheet::new(&listing, Handle::default(), Handle::default());
+
+        let beam = sheet.get("beam0.png").unwrap();
+        assert_eq!(beam.index, 0);
+        assert_eq!(beam.size(), Vec2::new(43., 31.));
+        assert_eq!(sheet.get("ufoRed.png").unwrap().index, 1);
+        assert!(sheet.get("ufoBlue.png").is_err());
+    }
+
+    #[test]
+    fn rejects_broken_listings() {
+        assert!(SheetListing::parse("<Nothing/>").is_err());
+        assert!(SheetListing::parse(
+            r#"<TextureAtlas imagePath="a.png"><SubTexture name="b" x="1" y="one" width="2" height="2"/>"#
+        )
+        .is_err());
+        assert!(SheetListing::parse(r#"<TextureAtlas><SubTexture name="b"/>"#).is_err());
+    }
+
+    #[test]
+    fn reads_png_sizes() {
+        let mut header = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        header.extend(1024_u32.to_be_bytes());
+        header.extend(512_u32.to_be_bytes());
+
+        assert_eq!(png_size(&header), Ok(Vec2::new(1024., 512.)));
+        assert!(png_size(b"GIF89a").is_err());
+    }
+}
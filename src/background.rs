@@ -0,0 +1,278 @@
+use bevy::prelude::*;
+use rand::{thread_rng, Rng};
+
+use crate::{loading::Loading, TimeScale, Wave, WinSize, BACKGROUND_TILES};
+
+/// Side of the square `BACKGROUND_TILES` images, in pixels
+pub const BACKGROUND_TILE_SIZE: f32 = 256.;
+/// pixels per second of the tiles, the star layers go faster
+const BACKGROUND_SPEED: f32 = 12.;
+/// tiles, the star layers are just in front; all behind the lasers (z 5)
+/// and ships (z 10), and in front of the camera's near plane
+const BACKGROUND_Z: f32 = 1.;
+/// waves past this one scroll no faster
+const FASTEST_WAVE: u32 = 10;
+
+/// A layer of stars: nearer layers have bigger, brighter and faster stars
+pub struct StarLayer {
+    /// stars per 100 000 square pixels of window
+    pub density: f32,
+    /// pixels per second on wave 1
+    pub speed: f32,
+    pub size: f32,
+    /// alpha of the palette colour
+    pub alpha: f32,
+}
+
+/// Far to near
+pub const STAR_LAYERS: [StarLayer; 3] = [
+    StarLayer {
+        density: 14.,
+        speed: 25.,
+        size: 1.5,
+        alpha: 0.4,
+    },
+    StarLayer {
+        density: 7.,
+        speed: 60.,
+        size: 2.,
+        alpha: 0.7,
+    },
+    StarLayer {
+        density: 3.,
+        speed: 140.,
+        size: 3.,
+        alpha: 1.,
+    },
+];
+
+/// Star colours of each layer, far to near, one palette per wave in turn
+const PALETTES: [[Color; 3]; 4] = [
+    [Color::WHITE, Color::WHITE, Color::rgb(1., 1., 0.85)],
+    [
+        Color::rgb(0.6, 0.8, 1.),
+        Color::rgb(0.75, 0.9, 1.),
+        Color::WHITE,
+    ],
+    [
+        Color::rgb(1., 0.6, 0.9),
+        Color::rgb(0.9, 0.7, 1.),
+        Color::rgb(1., 0.9, 1.),
+    ],
+    [
+        Color::rgb(1., 0.75, 0.5),
+        Color::rgb(1., 0.9, 0.6),
+        Color::WHITE,
+    ],
+];
+
+/// Scroll speed multiplier of `wave`.
+pub fn wave_speed(wave: u32) -> f32 {
+    1. + 0.1 * (wave.clamp(1, FASTEST_WAVE) - 1) as f32
+}
+
+/// Star colours of `wave`, far to near.
+pub fn wave_palette(wave: u32) -> [Color; 3] {
+    PALETTES[wave.saturating_sub(1) as usize % PALETTES.len()]
+}
+
+/// Index into `BACKGROUND_TILES` of `wave`.
+pub fn wave_tile(wave: u32) -> usize {
+    wave.saturating_sub(1) as usize % BACKGROUND_TILES.len()
+}
+
+fn star_colour(mut colour: Color, layer: &StarLayer) -> Color {
+    colour.set_a(layer.alpha);
+    colour
+}
+
+/// Columns and rows of tiles covering a `w` by `h` window while scrolling.
+pub fn tile_grid(w: f32, h: f32) -> (usize, usize) {
+    let columns = (w / BACKGROUND_TILE_SIZE).ceil() as usize;
+    let rows = (h / BACKGROUND_TILE_SIZE).ceil() as usize + 1;
+    (columns.max(1), rows)
+}
+
+/// Resource - the `BACKGROUND_TILES` images
+pub struct BackgroundImages(pub Vec<Handle<Image>>);
+
+impl FromWorld for BackgroundImages {
+    fn from_world(world: &mut World) -> Self {
+        // headless apps have no asset server
+        Self(
+            BACKGROUND_TILES
+                .iter()
+                .map(|path| {
+                    world
+                        .get_resource::<AssetServer>()
+                        .map_or_else(Handle::default, |asset_server| asset_server.load(*path))
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Component - a background image tile, in a grid that wraps around
+#[derive(Component)]
+pub struct BackgroundTile;
+
+/// Component - a star of the `STAR_LAYERS` layer with this index
+#[derive(Component)]
+pub struct Star(pub usize);
+
+/// Plugin - tiled background and parallax star layers scrolling down, with
+/// the speed and colours of the wave, laid out again when `WinSize` changes
+pub struct BackgroundPlugin;
+
+impl Plugin for BackgroundPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Wave>()
+            .init_resource::<TimeScale>()
+            .init_resource::<BackgroundImages>();
+
+        // wait for the tiles on the loading screen
+        let images = app.world.resource::<BackgroundImages>().0.clone();
+        if let Some(mut loading) = app.world.get_resource_mut::<Loading>() {
+            for image in images.iter() {
+                loading.add(image);
+            }
+        }
+
+        app.add_system(background_layout_system)
+            .add_system(background_wave_system.after(background_layout_system))
+            .add_system(background_scroll_system.after(background_wave_system));
+    }
+}
+
+/// Cover the window with tiles and stars, again when it changes size.
+fn background_layout_system(
+    mut commands: Commands,
+    win_size: Option<Res<WinSize>>,
+    wave: Res<Wave>,
+    images: Res<BackgroundImages>,
+    query: Query<Entity, Or<(With<BackgroundTile>, With<Star>)>>,
+) {
+    let win_size = match win_size {
+        Some(win_size) if win_size.is_changed() => win_size,
+        _ => return,
+    };
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let (columns, rows) = tile_grid(win_size.w, win_size.h);
+    let image = images.0.get(wave_tile(wave.0)).cloned().unwrap_or_default();
+    let left = -(columns as f32 * BACKGROUND_TILE_SIZE) / 2. + BACKGROUND_TILE_SIZE / 2.;
+    let bottom = -win_size.h / 2. + BACKGROUND_TILE_SIZE / 2.;
+    for column in 0..columns {
+        for row in 0..rows {
+            let x = left + column as f32 * BACKGROUND_TILE_SIZE;
+            let y = bottom + row as f32 * BACKGROUND_TILE_SIZE;
+            commands
+                .spawn_bundle(SpriteBundle {
+                    texture: image.clone(),
+                    transform: Transform::from_xyz(x, y, BACKGROUND_Z),
+                    ..Default::default()
+                })
+                .insert(BackgroundTile);
+        }
+    }
+
+    let mut rng = thread_rng();
+    let palette = wave_palette(wave.0);
+    for (index, layer) in STAR_LAYERS.iter().enumerate() {
+        let count = (win_size.w * win_size.h / 100_000. * layer.density).round() as usize;
+        for _ in 0..count {
+            let x = rng.gen_range(-0.5..0.5) * win_size.w;
+            let y = rng.gen_range(-0.5..0.5) * win_size.h;
+            commands
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: star_colour(palette[index], layer),
+                        custom_size: Some(Vec2::splat(layer.size)),
+                        ..Default::default()
+                    },
+                    transform: Transform::from_xyz(x, y, BACKGROUND_Z + 1. + index as f32),
+                    ..Default::default()
+                })
+                .insert(Star(index));
+        }
+    }
+}
+
+/// Each wave has its own tile and star colours.
+fn background_wave_system(
+    wave: Res<Wave>,
+    images: Res<BackgroundImages>,
+    mut tile_query: Query<&mut Handle<Image>, With<BackgroundTile>>,
+    mut star_query: Query<(&Star, &mut Sprite)>,
+) {
+    if !wave.is_changed() {
+        return;
+    }
+
+    let image = images.0.get(wave_tile(wave.0)).cloned().unwrap_or_default();
+    for mut texture in tile_query.iter_mut() {
+        *texture = image.clone();
+    }
+    let palette = wave_palette(wave.0);
+    for (star, mut sprite) in star_query.iter_mut() {
+        sprite.color = star_colour(palette[star.0], &STAR_LAYERS[star.0]);
+    }
+}
+
+/// Move everything down, wrapping what leaves the bottom back to the top.
+fn background_scroll_system(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    wave: Res<Wave>,
+    win_size: Option<Res<WinSize>>,
+    mut tile_query: Query<&mut Transform, (With<BackgroundTile>, Without<Star>)>,
+    mut star_query: Query<(&Star, &mut Transform), Without<BackgroundTile>>,
+) {
+    let win_size = match win_size {
+        Some(win_size) => win_size,
+        None => return,
+    };
+    let seconds = time.delta_seconds() * time_scale.0.max(0.) * wave_speed(wave.0);
+
+    let (_, rows) = tile_grid(win_size.w, win_size.h);
+    let bottom = -win_size.h / 2. - BACKGROUND_TILE_SIZE / 2.;
+    for mut transform in tile_query.iter_mut() {
+        transform.translation.y -= BACKGROUND_SPEED * seconds;
+        if transform.translation.y < bottom {
+            transform.translation.y += rows as f32 * BACKGROUND_TILE_SIZE;
+        }
+    }
+
+    let mut rng = thread_rng();
+    for (star, mut transform) in star_query.iter_mut() {
+        transform.translation.y -= STAR_LAYERS[star.0].speed * seconds;
+        if transform.translation.y < -win_size.h / 2. {
+            transform.translation.y += win_size.h;
+            transform.translation.x = rng.gen_range(-0.5..0.5) * win_size.w;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiles_cover_the_window_with_a_row_to_spare() {
+        assert_eq!(tile_grid(800., 720.), (4, 4));
+        assert_eq!(tile_grid(256., 256.), (1, 2));
+        assert_eq!(tile_grid(1920., 1080.), (8, 6));
+    }
+
+    #[test]
+    fn waves_cycle_the_look_and_speed_up() {
+        assert_eq!(wave_tile(1), 0);
+        assert_eq!(wave_tile(1 + BACKGROUND_TILES.len() as u32), 0);
+        assert_ne!(wave_palette(1), wave_palette(2));
+        assert_eq!(wave_speed(1), 1.);
+        assert!(wave_speed(5) > wave_speed(4));
+        assert_eq!(wave_speed(FASTEST_WAVE), wave_speed(FASTEST_WAVE + 5));
+    }
+}
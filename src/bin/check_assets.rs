@@ -0,0 +1,35 @@
+//! Check the game's assets before a release: every file the game loads
+//! exists and decodes, every sprite is in the sprite sheet and the hitboxes
+//! fit their sprites. Also lists the files the game never loads.
+//!
+//! `cargo run --bin check-assets [assets folder]`, exits with 1 on problems.
+
+use std::{path::PathBuf, process::ExitCode};
+
+use space_invaders::manifest::AssetManifest;
+
+fn main() -> ExitCode {
+    let assets = std::env::args()
+        .nth(1)
+        .map_or_else(|| PathBuf::from("assets"), PathBuf::from);
+
+    let report = AssetManifest::new().check(&assets);
+    for file in report.unused.iter() {
+        println!("unused: {file}");
+    }
+    for problem in report.problems.iter() {
+        println!("problem: {problem}");
+    }
+    println!(
+        "{}: {} problems, {} unused files",
+        assets.display(),
+        report.problems.len(),
+        report.unused.len()
+    );
+
+    if report.problems.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,302 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    enemy::EnemyCounting,
+    events::{EnemyDestroyed, PlayerHit, WaveCleared},
+    netcode::Online,
+    settings::Settings,
+    Paused, TimeScale, WinSize,
+};
+
+/// trauma added by each kind of event in a frame, from 0 to 1
+const ENEMY_TRAUMA: f32 = 0.2;
+const BIG_KILL_TRAUMA: f32 = 0.5;
+const PLAYER_HIT_TRAUMA: f32 = 0.7;
+/// trauma lost per second
+const TRAUMA_DECAY: f32 = 1.5;
+/// camera offset in pixels and roll in radians, at full trauma
+const MAX_SHAKE_OFFSET: f32 = 16.;
+const MAX_SHAKE_ROLL: f32 = 0.03;
+/// real seconds the game stands still after the last enemy of a wave
+const HIT_STOP_SECONDS: f32 = 0.12;
+const FLASH_SECONDS: f32 = 0.3;
+const FLASH_ALPHA: f32 = 0.4;
+/// in front of the world and the letterbox bars, behind the UI
+const FLASH_Z: f32 = 950.;
+
+/// Camera effects that can each be turned off, part of the user `Settings`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EffectSettings {
+    pub screen_shake: bool,
+    pub hit_stop: bool,
+    pub damage_flash: bool,
+}
+
+impl Default for EffectSettings {
+    fn default() -> Self {
+        Self {
+            screen_shake: true,
+            hit_stop: true,
+            damage_flash: true,
+        }
+    }
+}
+
+impl EffectSettings {
+    /// Names and switches of the effects, in settings menu order.
+    pub fn toggles(&mut self) -> [(&'static str, &mut bool); 3] {
+        [
+            ("Screen shake", &mut self.screen_shake),
+            ("Hit-stop", &mut self.hit_stop),
+            ("Damage flash", &mut self.damage_flash),
+        ]
+    }
+}
+
+/// Component - trauma-based shake of the camera, from 0 (still) to 1
+#[derive(Component, Clone, Debug, Default)]
+pub struct CameraEffects {
+    pub trauma: f32,
+    /// seconds of shaking, drives the noise
+    elapsed: f32,
+}
+
+impl CameraEffects {
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0., 1.);
+    }
+
+    /// Move on by `seconds`, returns the camera offset and roll.
+    pub fn advance(&mut self, seconds: f32) -> (Vec2, f32) {
+        self.elapsed += seconds;
+        self.trauma = (self.trauma - TRAUMA_DECAY * seconds).max(0.);
+
+        // squared, so light hits barely shake and big ones really do
+        let shake = self.trauma * self.trauma;
+        let t = self.elapsed;
+        let noise = |frequency: f32, phase: f32| {
+            ((t * frequency + phase).sin() + (t * frequency * 2.3 + phase * 1.7).sin()) / 2.
+        };
+        let offset = Vec2::new(noise(29., 0.), noise(31., 4.)) * MAX_SHAKE_OFFSET * shake;
+        (offset, noise(23., 8.) * MAX_SHAKE_ROLL * shake)
+    }
+}
+
+/// Resource - the time freeze after a big kill
+///
+/// Parks `TimeScale` at 0 like `Paused`, and waits out a pause before
+/// restoring it.
+#[derive(Default)]
+pub struct HitStop {
+    /// real seconds left
+    pub remaining: f32,
+    resume_scale: f32,
+}
+
+impl HitStop {
+    pub fn start(&mut self, time_scale: &mut TimeScale) {
+        if self.remaining <= 0. {
+            self.resume_scale = time_scale.0;
+        }
+        self.remaining = HIT_STOP_SECONDS;
+        time_scale.0 = 0.;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.remaining > 0.
+    }
+}
+
+/// Component - red overlay on the playfield, fading out
+#[derive(Component)]
+pub struct DamageFlash {
+    /// real seconds left
+    pub remaining: f32,
+}
+
+/// Plugin - screen shake, hit-stop and damage flash, each one switched by
+/// the `EffectSettings`
+pub struct CameraEffectsPlugin;
+
+impl Plugin for CameraEffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Settings>()
+            .init_resource::<TimeScale>()
+            .init_resource::<Paused>()
+            .init_resource::<HitStop>()
+            .add_event::<EnemyDestroyed>()
+            .add_event::<PlayerHit>()
+            .add_event::<WaveCleared>()
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                camera_event_system.after(EnemyCounting),
+            )
+            .add_system(hit_stop_system)
+            .add_system(camera_shake_system)
+            .add_system(damage_flash_system);
+    }
+}
+
+/// Shake on every hit, and freeze on the last enemy of a wave or flash on
+/// a player hit.
+fn camera_event_system(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    paused: Res<Paused>,
+    online: Option<Res<Online>>,
+    win_size: Option<Res<WinSize>>,
+    mut hit_stop: ResMut<HitStop>,
+    mut time_scale: ResMut<TimeScale>,
+    flash_query: Query<Entity, With<DamageFlash>>,
+    mut camera_query: Query<&mut CameraEffects>,
+    mut enemy_destroyed_events: EventReader<EnemyDestroyed>,
+    mut wave_cleared_events: EventReader<WaveCleared>,
+    mut player_hit_events: EventReader<PlayerHit>,
+) {
+    // a replayed tick's shake played the first time round
+    if online.as_deref().is_some_and(|online| online.resimulating) {
+        enemy_destroyed_events.clear();
+        wave_cleared_events.clear();
+        player_hit_events.clear();
+        return;
+    }
+
+    let effects = &settings.effects;
+    let mut trauma = 0.;
+
+    // once a frame however many enemies went down in it, so a volley or the
+    // console's kill_all doesn't pin the shake at full
+    if enemy_destroyed_events.iter().count() > 0 {
+        trauma += ENEMY_TRAUMA;
+    }
+    let big_kill = wave_cleared_events.iter().count() > 0;
+    if big_kill {
+        trauma += BIG_KILL_TRAUMA;
+    }
+    let mut player_hit = false;
+    for _ in player_hit_events.iter() {
+        trauma += PLAYER_HIT_TRAUMA;
+        player_hit = true;
+    }
+
+    if effects.screen_shake {
+        for mut camera in camera_query.iter_mut() {
+            camera.add_trauma(trauma);
+        }
+    }
+    // hit-stop runs on real time, which the peers of an online game don't share
+    if big_kill && effects.hit_stop && !paused.paused && online.is_none() {
+        hit_stop.start(&mut time_scale);
+    }
+    if let (true, true, Some(win_size)) = (player_hit, effects.damage_flash, win_size) {
+        for entity in flash_query.iter() {
+            commands.entity(entity).despawn();
+        }
+        let mut color = Color::RED;
+        color.set_a(FLASH_ALPHA);
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(Vec2::new(win_size.w, win_size.h)),
+                    ..Default::default()
+                },
+                transform: Transform::from_xyz(0., 0., FLASH_Z),
+                ..Default::default()
+            })
+            .insert(DamageFlash {
+                remaining: FLASH_SECONDS,
+            });
+    }
+}
+
+/// Count the freeze down in real time, and give the game its speed back.
+fn hit_stop_system(
+    time: Res<Time>,
+    paused: Res<Paused>,
+    mut hit_stop: ResMut<HitStop>,
+    mut time_scale: ResMut<TimeScale>,
+) {
+    if !hit_stop.is_active() || paused.paused {
+        return;
+    }
+
+    hit_stop.remaining -= time.delta_seconds();
+    if !hit_stop.is_active() {
+        time_scale.0 = hit_stop.resume_scale;
+    }
+}
+
+/// Offset and roll the camera by its trauma, settled back when shake is off.
+fn camera_shake_system(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut query: Query<(&mut CameraEffects, &mut Transform)>,
+) {
+    for (mut camera, mut transform) in query.iter_mut() {
+        if !settings.effects.screen_shake {
+            camera.trauma = 0.;
+        }
+        let (offset, roll) = camera.advance(time.delta_seconds());
+        transform.translation.x = offset.x;
+        transform.translation.y = offset.y;
+        transform.rotation = Quat::from_rotation_z(roll);
+    }
+}
+
+fn damage_flash_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut DamageFlash, &mut Sprite)>,
+) {
+    for (entity, mut flash, mut sprite) in query.iter_mut() {
+        flash.remaining -= time.delta_seconds();
+        if flash.remaining <= 0. {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        sprite
+            .color
+            .set_a(FLASH_ALPHA * flash.remaining / FLASH_SECONDS);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trauma_shakes_then_settles() {
+        let mut camera = CameraEffects::default();
+        assert_eq!(camera.advance(0.016), (Vec2::ZERO, 0.));
+
+        camera.add_trauma(0.7);
+        camera.add_trauma(0.7);
+        assert_eq!(camera.trauma, 1.);
+
+        let shaken = (0..10).any(|_| camera.advance(0.016).0.length() > 1.);
+        assert!(shaken);
+        assert!(camera.advance(0.016).0.length() <= MAX_SHAKE_OFFSET * 2_f32.sqrt());
+
+        for _ in 0..60 {
+            camera.advance(0.016);
+        }
+        assert_eq!(camera.trauma, 0.);
+        assert_eq!(camera.advance(0.016), (Vec2::ZERO, 0.));
+    }
+
+    #[test]
+    fn hit_stop_restores_the_scale_it_froze() {
+        let mut time_scale = TimeScale(0.5);
+        let mut hit_stop = HitStop::default();
+
+        hit_stop.start(&mut time_scale);
+        // a second kill during the freeze doesn't lose the real scale
+        hit_stop.start(&mut time_scale);
+        assert_eq!(time_scale.0, 0.);
+        assert!(hit_stop.is_active());
+        assert_eq!(hit_stop.resume_scale, 0.5);
+    }
+}
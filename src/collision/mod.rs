@@ -0,0 +1,406 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::BitOr,
+    sync::Arc,
+};
+
+use crate::{
+    animation::{AnimationClip, OnFinish, SpriteAnimation},
+    components::{
+        Enemy, Explosion, ExplosionKind, ExplosionToSpawn, FromPlayer, Laser, Player,
+        PreviousPosition, Shield, SpriteSize,
+    },
+    enemy::EnemyCounting,
+    events::{EnemyDestroyed, PlayerHit, ShieldDown, WaveCleared},
+    netcode::Online,
+    settings::Settings,
+    GameConfig, GameTextures, Movement, Players,
+};
+use bevy::{math::Vec3Swizzles, prelude::*};
+
+use self::{
+    shape::{AlphaMask, AlphaMaskSource, HitShape, Placed},
+    spatial_hash::SpatialHash,
+    swept::swept_overlap,
+};
+
+pub mod shape;
+pub mod spatial_hash;
+pub mod swept;
+
+/// Alpha (0..=255) above which a sprite pixel is solid
+const ALPHA_MASK_THRESHOLD: u8 = 127;
+/// Cap on shape tests per pair per tick for fast movers
+const MAX_SHAPE_SAMPLES: usize = 16;
+
+/// Plugin - collision detection, laser hits and the explosions they leave behind
+///
+/// The explosions play through the `AnimationPlugin`.
+pub struct CollisionPlugin;
+
+impl Plugin for CollisionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpatialHash>()
+            .init_resource::<Settings>()
+            .add_event::<CollisionEvent>()
+            .add_event::<EnemyDestroyed>()
+            .add_event::<PlayerHit>()
+            .add_event::<ShieldDown>()
+            .add_event::<WaveCleared>()
+            .add_system(alpha_mask_system.before(CollisionDetection))
+            // on this tick's positions, the same on every run
+            .add_system(
+                collision_detection_system
+                    .label(CollisionDetection)
+                    .after(Movement),
+            )
+            .add_system(laser_hit_system.after(CollisionDetection))
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                explosion_on_hit_system.after(EnemyCounting),
+            )
+            .add_system(explosion_to_spawn_system);
+    }
+}
+
+/// Collision layers, one bit per kind of collidable thing
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Layers(pub u32);
+
+impl Layers {
+    pub const NONE: Layers = Layers(0);
+    pub const PLAYER: Layers = Layers(1 << 0);
+    pub const PLAYER_LASER: Layers = Layers(1 << 1);
+    pub const ENEMY: Layers = Layers(1 << 2);
+    pub const ENEMY_LASER: Layers = Layers(1 << 3);
+    pub const POWER_UP: Layers = Layers(1 << 4);
+    pub const BUNKER: Layers = Layers(1 << 5);
+    pub const METEOR: Layers = Layers(1 << 6);
+
+    pub fn intersects(self, other: Layers) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl BitOr for Layers {
+    type Output = Layers;
+
+    fn bitor(self, rhs: Layers) -> Layers {
+        Layers(self.0 | rhs.0)
+    }
+}
+
+/// Component - what an entity is (`layer`) and what it wants to hit (`mask`)
+///
+/// Two colliders are tested against each other when either one's mask
+/// contains the other's layer. The hitbox is the entity's `SpriteSize`.
+#[derive(Clone, Copy, Component, Debug)]
+pub struct Collider {
+    pub layer: Layers,
+    pub mask: Layers,
+}
+
+impl Collider {
+    pub fn new(layer: Layers, mask: Layers) -> Self {
+        Self { layer, mask }
+    }
+
+    pub fn interacts_with(&self, other: &Collider) -> bool {
+        self.mask.intersects(other.layer) || other.mask.intersects(self.layer)
+    }
+}
+
+/// Event - two colliders overlap this frame
+#[derive(Clone, Copy, Debug)]
+pub struct CollisionEvent(pub Entity, pub Entity);
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, SystemLabel)]
+pub struct CollisionDetection;
+
+fn collision_detection_system(
+    mut spatial_hash: ResMut<SpatialHash>,
+    mut collision_events: EventWriter<CollisionEvent>,
+    query: Query<(
+        Entity,
+        &Transform,
+        &SpriteSize,
+        &Collider,
+        Option<&PreviousPosition>,
+        Option<&HitShape>,
+    )>,
+) {
+    // in a fixed order rather than storage order, so a laser meeting two
+    // targets in one tick hits the same one on every netplay peer
+    let mut colliders: Vec<_> = query.iter().collect();
+    colliders.sort_by(|(_, a_tf, _, a_collider, ..), (_, b_tf, _, b_collider, ..)| {
+        let (a, b) = (a_tf.translation, b_tf.translation);
+        (a_collider.layer.0.cmp(&b_collider.layer.0))
+            .then(a.x.total_cmp(&b.x))
+            .then(a.y.total_cmp(&b.y))
+    });
+
+    // broad-phase: rebuild the grid from this tick's positions, fast movers
+    // take up their whole path since the last move
+    spatial_hash.clear();
+    for (entity, tf, size, _, previous, _) in colliders {
+        let size = size.0 * tf.scale.xy();
+        let now = tf.translation.xy();
+        match previous {
+            Some(previous) => {
+                let prev = previous.0.xy();
+                spatial_hash.insert(entity, (prev + now) / 2., size + (now - prev).abs());
+            }
+            None => spatial_hash.insert(entity, now, size),
+        }
+    }
+
+    for (a, b) in spatial_hash.overlapping_pairs() {
+        let (a, b) = match (query.get(a), query.get(b)) {
+            (Ok(a), Ok(b)) => (a, b),
+            _ => continue,
+        };
+        let (a_entity, a_tf, a_size, a_collider, a_previous, a_shape) = a;
+        let (b_entity, b_tf, b_size, b_collider, b_previous, b_shape) = b;
+
+        if !a_collider.interacts_with(b_collider) {
+            continue;
+        }
+
+        let (a_scale, b_scale) = (a_tf.scale.xy(), b_tf.scale.xy());
+        let (a_box, b_box) = (a_size.0 * a_scale, b_size.0 * b_scale);
+        let (a_now, b_now) = (a_tf.translation.xy(), b_tf.translation.xy());
+        let a_prev = a_previous.map_or(a_now, |previous| previous.0.xy());
+        let b_prev = b_previous.map_or(b_now, |previous| previous.0.xy());
+
+        // narrow-phase: when did the boxes overlap during the tick (movers
+        // need their whole path checked, the grid only tested the ends)
+        let (t_enter, t_exit) = if a_previous.is_some() || b_previous.is_some() {
+            match swept_overlap(a_prev, a_now, a_box, b_prev, b_now, b_box) {
+                Some(span) => span,
+                None => continue,
+            }
+        } else {
+            (1., 1.)
+        };
+
+        // then the actual shapes, sampled along that span
+        if a_shape.is_some() || b_shape.is_some() {
+            let travel = ((a_now - a_prev) - (b_now - b_prev)).length() * (t_exit - t_enter);
+            let step = a_box.min_element().min(b_box.min_element()).max(1.) / 2.;
+            let samples = ((travel / step).ceil() as usize).clamp(1, MAX_SHAPE_SAMPLES);
+
+            let hit = (0..=samples).any(|i| {
+                let t = t_enter + (t_exit - t_enter) * i as f32 / samples as f32;
+                let a = Placed::new(a_shape, a_size.0, a_prev.lerp(a_now, t), a_scale);
+                let b = Placed::new(b_shape, b_size.0, b_prev.lerp(b_now, t), b_scale);
+                a.intersects(&b)
+            });
+            if !hit {
+                continue;
+            }
+        }
+
+        collision_events.send(CollisionEvent(a_entity, b_entity));
+    }
+}
+
+/// Turn loaded `AlphaMaskSource` images into `HitShape::Mask`s, one mask per sprite.
+fn alpha_mask_system(
+    mut commands: Commands,
+    mut masks: Local<HashMap<(Handle<Image>, usize), Arc<AlphaMask>>>,
+    images: Option<Res<Assets<Image>>>,
+    query: Query<(Entity, &AlphaMaskSource)>,
+) {
+    let images = match images {
+        Some(images) => images,
+        None => return,
+    };
+
+    for (entity, source) in query.iter() {
+        let key = (source.image.clone(), source.sprite.index);
+        let mask = match masks.get(&key) {
+            Some(mask) => Some(mask.clone()),
+            None => images
+                .get(&source.image)
+                .and_then(|image| {
+                    AlphaMask::from_image(image, &source.sprite, ALPHA_MASK_THRESHOLD)
+                })
+                .map(|mask| {
+                    let mask = Arc::new(mask);
+                    masks.insert(key, mask.clone());
+                    mask
+                }),
+        };
+
+        if let Some(mask) = mask {
+            commands
+                .entity(entity)
+                .insert(HitShape::Mask(mask))
+                .remove::<AlphaMaskSource>();
+        }
+    }
+}
+
+fn laser_hit_system(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut enemy_destroyed_events: EventWriter<EnemyDestroyed>,
+    mut player_hit_events: EventWriter<PlayerHit>,
+    mut shield_down_events: EventWriter<ShieldDown>,
+    players: Res<Players>,
+    config: Res<GameConfig>,
+    laser_query: Query<(&Collider, Option<&FromPlayer>), With<Laser>>,
+    target_query: Query<
+        (&Transform, Option<&Enemy>, Option<&Player>, Option<&Shield>),
+        Without<Laser>,
+    >,
+) {
+    let mut despawned_entities: HashSet<Entity> = HashSet::new();
+    // the removal waits for the end of the stage, one hit per shield
+    let mut shields_down: HashSet<Entity> = HashSet::new();
+
+    for CollisionEvent(a, b) in collision_events.iter() {
+        let (laser_entity, target_entity) = if laser_query.contains(*a) {
+            (*a, *b)
+        } else {
+            (*b, *a)
+        };
+
+        if despawned_entities.contains(&laser_entity)
+            || despawned_entities.contains(&target_entity)
+        {
+            continue;
+        }
+
+        let ((laser_collider, from_player), (target_tf, enemy, player, shield)) = match (
+            laser_query.get(laser_entity),
+            target_query.get(target_entity),
+        ) {
+            (Ok(laser), Ok(target)) => (laser, target),
+            _ => continue,
+        };
+
+        if enemy.is_some() && laser_collider.layer.intersects(Layers::PLAYER_LASER) {
+            enemy_destroyed_events.send(EnemyDestroyed {
+                enemy: target_entity,
+                position: target_tf.translation,
+                by_player: from_player.map(|from| from.0),
+            });
+        } else if let Some(player) = player {
+            let hit = if laser_collider.layer.intersects(Layers::ENEMY_LASER) {
+                true
+            } else {
+                // a player's own lasers never hit them
+                config.friendly_fire && from_player.is_some_and(|from| from.0 != player.0)
+            };
+            if !hit || players.0[player.0].god {
+                continue;
+            }
+
+            // the shield takes the hit instead of the ship
+            if shield.is_some() && shields_down.insert(target_entity) {
+                commands.entity(target_entity).remove::<Shield>();
+                shield_down_events.send(ShieldDown {
+                    player: target_entity,
+                    position: target_tf.translation,
+                });
+                commands.entity(laser_entity).despawn();
+                despawned_entities.insert(laser_entity);
+                continue;
+            }
+
+            player_hit_events.send(PlayerHit {
+                player: target_entity,
+                number: player.0,
+                position: target_tf.translation,
+            });
+        } else {
+            continue;
+        }
+
+        // players take their engine flame along
+        commands.entity(target_entity).despawn_recursive();
+        despawned_entities.insert(target_entity);
+
+        commands.entity(laser_entity).despawn();
+        despawned_entities.insert(laser_entity);
+    }
+}
+
+/// Pick the explosion by what was destroyed: a burst for an enemy, a big
+/// blast for the last one of a wave and a fireball in the ship's colour for
+/// a player.
+fn explosion_on_hit_system(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    online: Option<Res<Online>>,
+    mut enemy_destroyed_events: EventReader<EnemyDestroyed>,
+    mut player_hit_events: EventReader<PlayerHit>,
+    mut wave_cleared_events: EventReader<WaveCleared>,
+) {
+    // a replayed tick's explosions played the first time round
+    if online.is_some_and(|online| online.resimulating) {
+        enemy_destroyed_events.clear();
+        player_hit_events.clear();
+        wave_cleared_events.clear();
+        return;
+    }
+
+    // the wave's last kill is the final event of the frame that clears it
+    let wave_cleared = wave_cleared_events.iter().count() > 0;
+    let events: Vec<_> = enemy_destroyed_events.iter().collect();
+    for (i, event) in events.iter().enumerate() {
+        let explosion = if wave_cleared && i + 1 == events.len() {
+            ExplosionToSpawn {
+                scale: 2.,
+                ..ExplosionToSpawn::new(ExplosionKind::Blast, event.position)
+            }
+        } else {
+            ExplosionToSpawn::new(ExplosionKind::Burst, event.position)
+        };
+        commands.spawn().insert(explosion);
+    }
+    for event in player_hit_events.iter() {
+        let colour = settings.ship_colours[event.number];
+        commands.spawn().insert(ExplosionToSpawn {
+            scale: 1.5,
+            tint: colour.tint(),
+            ..ExplosionToSpawn::new(ExplosionKind::Fireball, event.position)
+        });
+    }
+}
+
+fn explosion_to_spawn_system(
+    mut commands: Commands,
+    game_textures: Option<Res<GameTextures>>,
+    query: Query<(Entity, &ExplosionToSpawn)>,
+) {
+    let game_textures = match game_textures {
+        Some(game_textures) => game_textures,
+        None => return,
+    };
+    for (explosion_spawn_entity, explosion_to_spawn) in query.iter() {
+        let kind = explosion_to_spawn.kind;
+        let sheet = kind.sheet();
+        commands
+            .spawn_bundle(SpriteSheetBundle {
+                sprite: TextureAtlasSprite {
+                    color: explosion_to_spawn.tint,
+                    ..Default::default()
+                },
+                texture_atlas: game_textures.explosion(kind),
+                transform: Transform {
+                    translation: explosion_to_spawn.position,
+                    scale: Vec3::splat(explosion_to_spawn.scale),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(Explosion(kind))
+            .insert(SpriteAnimation::new(
+                AnimationClip::new(0..sheet.frames, sheet.fps).then(OnFinish::Despawn),
+            ));
+
+        commands.entity(explosion_spawn_entity).despawn();
+    }
+}
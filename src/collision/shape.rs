@@ -0,0 +1,421 @@
+use std::sync::Arc;
+
+use bevy::{
+    prelude::{Component, Handle, Image, Vec2},
+    render::render_resource::TextureFormat,
+};
+
+use crate::atlas::SheetSprite;
+
+/// Component - collision shape, in unscaled sprite pixels around the sprite center
+///
+/// Entities without one collide as their full `SpriteSize` box. Sprites are
+/// never rotated, so only translation and scale are applied.
+#[derive(Clone, Component, Debug)]
+pub enum HitShape {
+    Circle { radius: f32 },
+    Capsule { a: Vec2, b: Vec2, radius: f32 },
+    /// Convex polygon, vertices in order (either winding)
+    Polygon(Vec<Vec2>),
+    Mask(Arc<AlphaMask>),
+}
+
+impl HitShape {
+    /// Vertical capsule filling a `(width, height)` sprite, for lasers.
+    pub fn vertical_capsule(size: (f32, f32)) -> Self {
+        let radius = size.0 / 2.;
+        let half_length = (size.1 / 2. - radius).max(0.);
+        HitShape::Capsule {
+            a: Vec2::new(0., -half_length),
+            b: Vec2::new(0., half_length),
+            radius,
+        }
+    }
+
+    pub fn polygon(points: &[(f32, f32)]) -> Self {
+        HitShape::Polygon(points.iter().map(|&(x, y)| Vec2::new(x, y)).collect())
+    }
+}
+
+/// Component - build a `HitShape::Mask` from this sprite of a sheet image
+/// once the image has loaded
+#[derive(Clone, Component)]
+pub struct AlphaMaskSource {
+    pub image: Handle<Image>,
+    pub sprite: SheetSprite,
+}
+
+/// Opaque pixels of a sprite, row 0 at the top as in the PNG
+#[derive(Debug)]
+pub struct AlphaMask {
+    pub width: usize,
+    pub height: usize,
+    opaque: Vec<bool>,
+}
+
+impl AlphaMask {
+    /// Alpha above `threshold` (0..=255) counts as solid.
+    pub fn from_rgba(width: usize, height: usize, rgba: &[u8], threshold: u8) -> Self {
+        let opaque = rgba
+            .chunks_exact(4)
+            .take(width * height)
+            .map(|pixel| pixel[3] > threshold)
+            .collect();
+        Self {
+            width,
+            height,
+            opaque,
+        }
+    }
+
+    /// Mask of `sprite` within the sheet `image`, `None` for images not
+    /// stored as 8-bit RGBA or sprites not wholly inside the image.
+    pub fn from_image(image: &Image, sprite: &SheetSprite, threshold: u8) -> Option<Self> {
+        match image.texture_descriptor.format {
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => {
+                let image_width = image.texture_descriptor.size.width as usize;
+                let stride = image_width * 4;
+                let (x, y) = (sprite.rect.min.x as usize, sprite.rect.min.y as usize);
+                let size = sprite.size();
+                let (width, height) = (size.x as usize, size.y as usize);
+                let image_height = image.data.len().checked_div(stride)?;
+                if x + width > image_width || y + height > image_height {
+                    return None;
+                }
+
+                let rgba: Vec<u8> = image
+                    .data
+                    .chunks_exact(stride)
+                    .skip(y)
+                    .take(height)
+                    .flat_map(|row| &row[x * 4..(x + width) * 4])
+                    .copied()
+                    .collect();
+                Some(Self::from_rgba(width, height, &rgba, threshold))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_opaque(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.opaque[y * self.width + x]
+    }
+
+    fn size(&self) -> Vec2 {
+        Vec2::new(self.width as f32, self.height as f32)
+    }
+}
+
+/// A shape placed in the world
+pub enum Placed<'a> {
+    /// Points within `radius` of a convex polygon (1 point: circle, 2: capsule)
+    Convex { points: Vec<Vec2>, radius: f32 },
+    Mask {
+        mask: &'a AlphaMask,
+        center: Vec2,
+        scale: Vec2,
+    },
+}
+
+impl<'a> Placed<'a> {
+    /// `shape` (or the `size` box when `None`) at `center`, scaled by `scale`.
+    pub fn new(shape: Option<&'a HitShape>, size: Vec2, center: Vec2, scale: Vec2) -> Self {
+        let place = |point: Vec2| center + point * scale;
+        // radii only follow the x scale, sprites are scaled uniformly
+        match shape {
+            None => {
+                let half = size * scale / 2.;
+                Placed::Convex {
+                    points: vec![
+                        center + Vec2::new(-half.x, -half.y),
+                        center + Vec2::new(half.x, -half.y),
+                        center + Vec2::new(half.x, half.y),
+                        center + Vec2::new(-half.x, half.y),
+                    ],
+                    radius: 0.,
+                }
+            }
+            Some(HitShape::Circle { radius }) => Placed::Convex {
+                points: vec![center],
+                radius: radius * scale.x,
+            },
+            Some(HitShape::Capsule { a, b, radius }) => Placed::Convex {
+                points: vec![place(*a), place(*b)],
+                radius: radius * scale.x,
+            },
+            Some(HitShape::Polygon(points)) => Placed::Convex {
+                points: points.iter().map(|point| place(*point)).collect(),
+                radius: 0.,
+            },
+            Some(HitShape::Mask(mask)) => Placed::Mask {
+                mask,
+                center,
+                scale,
+            },
+        }
+    }
+
+    fn bounds(&self) -> (Vec2, Vec2) {
+        match self {
+            Placed::Convex { points, radius } => {
+                let min = points.iter().fold(Vec2::splat(f32::MAX), |min, p| min.min(*p));
+                let max = points.iter().fold(Vec2::splat(f32::MIN), |max, p| max.max(*p));
+                (min - *radius, max + *radius)
+            }
+            Placed::Mask {
+                mask,
+                center,
+                scale,
+            } => {
+                let half = mask.size() * scale.abs() / 2.;
+                (*center - half, *center + half)
+            }
+        }
+    }
+
+    /// Does this shape contain `point` (strictly)?
+    fn contains(&self, point: Vec2) -> bool {
+        match self {
+            Placed::Convex { points, radius } => {
+                convex_contains(points, point) || convex_point_distance(points, point) < *radius
+            }
+            Placed::Mask {
+                mask,
+                center,
+                scale,
+            } => {
+                let pixel = (point - *center) / *scale + mask.size() / 2.;
+                pixel.x >= 0.
+                    && pixel.y >= 0.
+                    && mask.is_opaque(pixel.x as usize, (mask.size().y - pixel.y) as usize)
+            }
+        }
+    }
+
+    pub fn intersects(&self, other: &Placed) -> bool {
+        match (self, other) {
+            (
+                Placed::Convex {
+                    points: a,
+                    radius: a_radius,
+                },
+                Placed::Convex {
+                    points: b,
+                    radius: b_radius,
+                },
+            ) => convex_overlap(a, b) || convex_distance(a, b) < a_radius + b_radius,
+            (
+                Placed::Mask {
+                    mask,
+                    center,
+                    scale,
+                },
+                other,
+            ) => mask_intersects(mask, *center, *scale, other),
+            (convex, mask) => mask.intersects(convex),
+        }
+    }
+}
+
+/// Walk the mask's opaque pixels that fall in `other`'s bounds.
+fn mask_intersects(mask: &AlphaMask, center: Vec2, scale: Vec2, other: &Placed) -> bool {
+    let (other_min, other_max) = other.bounds();
+    let origin = center - mask.size() * scale / 2.;
+
+    // pixel range covered by the other shape's bounds (x right, row down)
+    let to_pixel = |world: Vec2| (world - origin) / scale;
+    let (low, high) = (to_pixel(other_min), to_pixel(other_max));
+    let (low, high) = (low.min(high).max(Vec2::ZERO), low.max(high).min(mask.size()));
+
+    for column in low.x as usize..high.x.ceil() as usize {
+        for row_from_bottom in low.y as usize..high.y.ceil() as usize {
+            let row = mask.height - 1 - row_from_bottom.min(mask.height - 1);
+            if !mask.is_opaque(column, row) {
+                continue;
+            }
+            let pixel_center = Vec2::new(column as f32 + 0.5, row_from_bottom as f32 + 0.5);
+            if other.contains(origin + pixel_center * scale) {
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+/// Strictly inside a convex polygon of 3+ points.
+fn convex_contains(points: &[Vec2], point: Vec2) -> bool {
+    if points.len() < 3 {
+        return false;
+    }
+    let mut sign = 0.;
+    for (i, a) in points.iter().enumerate() {
+        let b = points[(i + 1) % points.len()];
+        let cross = (b - *a).perp_dot(point - *a);
+        if cross == 0. || (sign != 0. && cross.signum() != sign) {
+            return false;
+        }
+        sign = cross.signum();
+    }
+    true
+}
+
+fn segment_point_distance(a: Vec2, b: Vec2, point: Vec2) -> f32 {
+    let ab = b - a;
+    let t = if ab == Vec2::ZERO {
+        0.
+    } else {
+        ((point - a).dot(ab) / ab.length_squared()).clamp(0., 1.)
+    };
+    (a + ab * t).distance(point)
+}
+
+fn edges(points: &[Vec2]) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+    let count = if points.len() == 2 { 1 } else { points.len() };
+    (0..count).map(move |i| (points[i], points[(i + 1) % points.len()]))
+}
+
+fn convex_point_distance(points: &[Vec2], point: Vec2) -> f32 {
+    edges(points)
+        .map(|(a, b)| segment_point_distance(a, b, point))
+        .fold(f32::MAX, f32::min)
+}
+
+/// Separating axis test, touching does not count as overlapping.
+fn convex_overlap(a: &[Vec2], b: &[Vec2]) -> bool {
+    let axes = |points: &[Vec2]| -> Vec<Vec2> {
+        let mut axes: Vec<Vec2> = edges(points).map(|(p, q)| (q - p).perp()).collect();
+        // a lone segment can also be separated along itself
+        if points.len() == 2 {
+            axes.push(points[1] - points[0]);
+        }
+        axes
+    };
+
+    let project = |points: &[Vec2], axis: Vec2| {
+        points.iter().fold((f32::MAX, f32::MIN), |(min, max), p| {
+            let d = p.dot(axis);
+            (min.min(d), max.max(d))
+        })
+    };
+
+    let all_axes: Vec<Vec2> = axes(a)
+        .into_iter()
+        .chain(axes(b))
+        .filter(|axis| *axis != Vec2::ZERO)
+        .collect();
+    if all_axes.is_empty() {
+        return false;
+    }
+
+    all_axes.into_iter().all(|axis| {
+        let (a_min, a_max) = project(a, axis);
+        let (b_min, b_max) = project(b, axis);
+        a_max > b_min && b_max > a_min
+    })
+}
+
+/// Distance between two convex polygons (points and segments allowed) that
+/// do not overlap.
+fn convex_distance(a: &[Vec2], b: &[Vec2]) -> f32 {
+    let a_to_b = a.iter().map(|p| convex_point_distance(b, *p));
+    let b_to_a = b.iter().map(|p| convex_point_distance(a, *p));
+    a_to_b.chain(b_to_a).fold(f32::MAX, f32::min)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placed(shape: Option<&HitShape>, center: (f32, f32)) -> Placed<'_> {
+        Placed::new(
+            shape,
+            Vec2::new(20., 20.),
+            Vec2::new(center.0, center.1),
+            Vec2::ONE,
+        )
+    }
+
+    #[test]
+    fn circle_misses_box_corner() {
+        let circle = HitShape::Circle { radius: 10. };
+
+        // boxes overlap at the corner, the circle does not reach it
+        assert!(!placed(Some(&circle), (0., 0.)).intersects(&placed(None, (18., 18.))));
+        assert!(placed(Some(&circle), (0., 0.)).intersects(&placed(None, (18., 0.))));
+    }
+
+    #[test]
+    fn capsule_hits_along_its_length() {
+        let capsule = HitShape::vertical_capsule((4., 40.));
+        let circle = HitShape::Circle { radius: 3. };
+
+        assert!(placed(Some(&capsule), (0., 0.)).intersects(&placed(Some(&circle), (4., 15.))));
+        assert!(!placed(Some(&capsule), (0., 0.)).intersects(&placed(Some(&circle), (4., 25.))));
+    }
+
+    #[test]
+    fn box_inside_polygon_hits() {
+        let hull = HitShape::polygon(&[(-30., -30.), (30., -30.), (0., 30.)]);
+
+        assert!(placed(Some(&hull), (0., 0.)).intersects(&placed(None, (0., -10.))));
+    }
+
+    #[test]
+    fn segment_crossing_polygon_hits() {
+        let wide = HitShape::polygon(&[(-30., -2.), (30., -2.), (30., 2.), (-30., 2.)]);
+        let capsule = HitShape::Capsule {
+            a: Vec2::new(0., -20.),
+            b: Vec2::new(0., 20.),
+            radius: 0.5,
+        };
+
+        assert!(placed(Some(&wide), (0., 0.)).intersects(&placed(Some(&capsule), (0., 0.))));
+    }
+
+    #[test]
+    fn mask_only_hits_opaque_pixels() {
+        // 4x4 mask, only the top left pixel is solid
+        let mut rgba = vec![0; 4 * 4 * 4];
+        rgba[3] = 255;
+        let mask = HitShape::Mask(Arc::new(AlphaMask::from_rgba(4, 4, &rgba, 127)));
+        let dot = HitShape::Circle { radius: 0.25 };
+
+        // sprite spans -2..2, top left pixel is centered at (-1.5, 1.5)
+        let hit = |at| placed(Some(&mask), (0., 0.)).intersects(&placed(Some(&dot), at));
+        assert!(hit((-1.5, 1.5)));
+        assert!(!hit((1.5, -1.5)));
+        assert!(!hit((-1.5, -1.5)));
+    }
+
+    #[test]
+    fn mask_of_a_sprite_past_the_image_edge_is_none() {
+        use bevy::{
+            render::render_resource::{Extent3d, TextureDimension},
+            sprite::Rect,
+        };
+
+        let image = Image::new_fill(
+            Extent3d {
+                width: 4,
+                height: 4,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &[255; 4],
+            TextureFormat::Rgba8UnormSrgb,
+        );
+        let sprite = |min: (f32, f32), max: (f32, f32)| SheetSprite {
+            index: 0,
+            rect: Rect {
+                min: Vec2::new(min.0, min.1),
+                max: Vec2::new(max.0, max.1),
+            },
+        };
+
+        let mask = AlphaMask::from_image(&image, &sprite((2., 2.), (4., 4.)), 127).unwrap();
+        assert!(mask.is_opaque(1, 1));
+        assert!(AlphaMask::from_image(&image, &sprite((2., 0.), (6., 2.)), 127).is_none());
+        assert!(AlphaMask::from_image(&image, &sprite((0., 3.), (2., 5.)), 127).is_none());
+    }
+}
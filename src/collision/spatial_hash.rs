@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use bevy::prelude::{Entity, Vec2};
+
+/// Default cell size, about the size of a scaled enemy sprite
+pub const DEFAULT_CELL_SIZE: f32 = 64.;
+
+struct Entry {
+    entity: Entity,
+    min: Vec2,
+    max: Vec2,
+}
+
+/// Resource - uniform grid broad-phase, rebuilt every tick
+///
+/// Each box is stored in every cell it touches, so only boxes sharing a
+/// cell are ever compared.
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    entries: Vec<Entry>,
+}
+
+impl Default for SpatialHash {
+    fn default() -> Self {
+        Self::new(DEFAULT_CELL_SIZE)
+    }
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.entries.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn cell(&self, point: Vec2) -> (i32, i32) {
+        (
+            (point.x / self.cell_size).floor() as i32,
+            (point.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    /// Add a box given by its center and full size.
+    pub fn insert(&mut self, entity: Entity, center: Vec2, size: Vec2) {
+        let (min, max) = (center - size / 2., center + size / 2.);
+        let index = self.entries.len();
+        self.entries.push(Entry { entity, min, max });
+
+        let (x_min, y_min) = self.cell(min);
+        let (x_max, y_max) = self.cell(max);
+        for x in x_min..=x_max {
+            for y in y_min..=y_max {
+                self.cells.entry((x, y)).or_default().push(index);
+            }
+        }
+    }
+
+    /// Every pair of overlapping boxes, once each, in insertion order.
+    pub fn overlapping_pairs(&self) -> Vec<(Entity, Entity)> {
+        let mut pairs = Vec::new();
+
+        for (cell, indices) in self.cells.iter() {
+            for (i, &a) in indices.iter().enumerate() {
+                for &b in indices.iter().skip(i + 1) {
+                    let (a_entry, b_entry) = (&self.entries[a], &self.entries[b]);
+                    if !overlaps(a_entry, b_entry) {
+                        continue;
+                    }
+
+                    // a pair sharing several cells is only reported by the
+                    // cell holding the min corner of the overlap
+                    let corner = a_entry.min.max(b_entry.min);
+                    if self.cell(corner) == *cell {
+                        pairs.push((a.min(b), a.max(b)));
+                    }
+                }
+            }
+        }
+
+        pairs.sort_unstable();
+        pairs
+            .into_iter()
+            .map(|(a, b)| (self.entries[a].entity, self.entries[b].entity))
+            .collect()
+    }
+}
+
+/// Same test as `bevy::sprite::collide_aabb::collide` (touching edges do not count)
+fn overlaps(a: &Entry, b: &Entry) -> bool {
+    a.min.x < b.max.x && a.max.x > b.min.x && a.min.y < b.max.y && a.max.y > b.min.y
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_each_overlapping_pair_once() {
+        let mut hash = SpatialHash::new(10.);
+        let (a, b, c) = (Entity::from_raw(0), Entity::from_raw(1), Entity::from_raw(2));
+
+        // a and b span several cells and overlap in more than one of them
+        hash.insert(a, Vec2::new(0., 0.), Vec2::new(30., 30.));
+        hash.insert(b, Vec2::new(10., 10.), Vec2::new(30., 30.));
+        hash.insert(c, Vec2::new(100., 100.), Vec2::new(5., 5.));
+
+        assert_eq!(hash.overlapping_pairs(), vec![(a, b)]);
+    }
+
+    #[test]
+    fn touching_edges_do_not_overlap() {
+        let mut hash = SpatialHash::new(10.);
+        hash.insert(Entity::from_raw(0), Vec2::new(0., 0.), Vec2::new(10., 10.));
+        hash.insert(Entity::from_raw(1), Vec2::new(10., 0.), Vec2::new(10., 10.));
+
+        assert!(hash.overlapping_pairs().is_empty());
+    }
+}
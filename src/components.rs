@@ -1,25 +1,35 @@
-use std::default;
+use bevy::prelude::{Color, Component, Vec2, Vec3};
 
-use bevy::{
-    prelude::{Component, Vec2, Vec3},
-    time::Timer,
-};
+use crate::{BLAST_SHEET, EXPLOSION_SHEET, FIREBALL_SHEET};
 
-#[derive(Component)]
+#[derive(Clone, Component)]
 pub struct Velocity {
     pub x: f32,
     pub y: f32,
 }
 
-#[derive(Component)]
+#[derive(Clone, Component)]
 pub struct Movable {
     pub auto_despawn: bool,
 }
-#[derive(Component)]
-pub struct Player;
 
-#[derive(Component)]
-pub struct FromPlayer;
+/// Component - translation before the last move, for swept collision
+#[derive(Clone, Component)]
+pub struct PreviousPosition(pub Vec3);
+/// Component - player ship, with the player number (0 for player one)
+#[derive(Clone, Copy, Component, Debug, PartialEq, Eq)]
+pub struct Player(pub usize);
+
+/// Component - fired by the player with this number
+#[derive(Clone, Copy, Component, Debug, PartialEq, Eq)]
+pub struct FromPlayer(pub usize);
+
+/// Component - shield of a player, taking the next hit instead of the ship
+/// until `GameClock` reaches `until`
+#[derive(Clone, Component)]
+pub struct Shield {
+    pub until: f64,
+}
 
 #[derive(Component)]
 pub struct Enemy;
@@ -30,7 +40,7 @@ pub struct FromEnemy;
 #[derive(Component)]
 pub struct Laser;
 
-#[derive(Component)]
+#[derive(Clone, Component)]
 pub struct SpriteSize(pub Vec2);
 
 impl From<(f32, f32)> for SpriteSize {
@@ -39,17 +49,82 @@ impl From<(f32, f32)> for SpriteSize {
     }
 }
 
-#[derive(Component)]
-pub struct Explosion;
+/// Kinds of explosion, each played from its own sheet
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ExplosionKind {
+    /// small and quick, for a destroyed enemy
+    Burst,
+    /// for a destroyed player ship
+    Fireball,
+    /// large and slow, for the last enemy of a wave
+    Blast,
+}
 
-#[derive(Component)]
-pub struct ExplosionToSpawn(pub Vec3);
+/// Grid of an explosion sheet and how fast its frames play
+#[derive(Clone, Copy, Debug)]
+pub struct ExplosionSheet {
+    pub path: &'static str,
+    pub tile_size: f32,
+    pub columns: usize,
+    pub frames: usize,
+    /// frames per second
+    pub fps: f32,
+}
 
+impl ExplosionKind {
+    pub const ALL: [ExplosionKind; 3] = [
+        ExplosionKind::Burst,
+        ExplosionKind::Fireball,
+        ExplosionKind::Blast,
+    ];
+
+    pub fn sheet(&self) -> ExplosionSheet {
+        match self {
+            ExplosionKind::Burst => ExplosionSheet {
+                path: EXPLOSION_SHEET,
+                tile_size: 64.,
+                columns: 4,
+                frames: 16,
+                fps: 20.,
+            },
+            ExplosionKind::Fireball => ExplosionSheet {
+                path: FIREBALL_SHEET,
+                tile_size: 64.,
+                columns: 4,
+                frames: 16,
+                fps: 25.,
+            },
+            ExplosionKind::Blast => ExplosionSheet {
+                path: BLAST_SHEET,
+                tile_size: 64.,
+                columns: 4,
+                frames: 16,
+                fps: 14.,
+            },
+        }
+    }
+}
+
+/// Component - a playing explosion, its `SpriteAnimation` despawns it
 #[derive(Component)]
-pub struct ExplosionTimer(pub Timer);
+pub struct Explosion(pub ExplosionKind);
+
+/// Component - explosion spawned on the next update, then despawned
+#[derive(Clone, Copy, Component, Debug)]
+pub struct ExplosionToSpawn {
+    pub position: Vec3,
+    pub kind: ExplosionKind,
+    pub scale: f32,
+    pub tint: Color,
+}
 
-impl Default for ExplosionTimer {
-    fn default() -> Self {
-        Self(Timer::from_seconds(0.05, true))
+impl ExplosionToSpawn {
+    pub fn new(kind: ExplosionKind, position: Vec3) -> Self {
+        Self {
+            position,
+            kind,
+            scale: 1.,
+            tint: Color::WHITE,
+        }
     }
 }
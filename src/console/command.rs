@@ -0,0 +1,133 @@
+use std::str::FromStr;
+
+use crate::{enemy::EnemyKind, events::PowerUpKind, GameConfig};
+
+pub const HELP: &str = "commands: spawn enemy <kind> <x> <y> | god on|off | wave <n> \
+    | give powerup <name> | set <config_key> <value> | kill_all | timescale <f> | help";
+
+/// A parsed console line
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConsoleCommand {
+    SpawnEnemy { kind: EnemyKind, x: f32, y: f32 },
+    God(bool),
+    Wave(u32),
+    GivePowerUp(PowerUpKind),
+    Set { key: String, value: String },
+    KillAll,
+    TimeScale(f32),
+    Help,
+}
+
+impl FromStr for ConsoleCommand {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        match words.as_slice() {
+            ["spawn", "enemy", kind, x, y] => Ok(ConsoleCommand::SpawnEnemy {
+                kind: EnemyKind::from_name(kind).ok_or_else(|| {
+                    let kinds: Vec<_> = EnemyKind::ALL.iter().map(|kind| kind.name()).collect();
+                    format!("unknown enemy kind {kind} (one of: {})", kinds.join(", "))
+                })?,
+                x: number(x)?,
+                y: number(y)?,
+            }),
+            ["god", "on"] => Ok(ConsoleCommand::God(true)),
+            ["god", "off"] => Ok(ConsoleCommand::God(false)),
+            ["wave", n] => Ok(ConsoleCommand::Wave(number(n)?)),
+            ["give", "powerup", name] => PowerUpKind::from_name(name)
+                .map(ConsoleCommand::GivePowerUp)
+                .ok_or_else(|| {
+                    let kinds: Vec<_> = PowerUpKind::ALL.iter().map(|kind| kind.name()).collect();
+                    format!("unknown powerup {name} (one of: {})", kinds.join(", "))
+                }),
+            ["set", key, value] => {
+                if GameConfig::KEYS.contains(key) {
+                    Ok(ConsoleCommand::Set {
+                        key: key.to_string(),
+                        value: value.to_string(),
+                    })
+                } else {
+                    Err(format!(
+                        "unknown config key {key} (one of: {})",
+                        GameConfig::KEYS.join(", ")
+                    ))
+                }
+            }
+            ["kill_all"] => Ok(ConsoleCommand::KillAll),
+            ["timescale", scale] => {
+                let scale: f32 = number(scale)?;
+                if scale >= 0. {
+                    Ok(ConsoleCommand::TimeScale(scale))
+                } else {
+                    Err("timescale must not be negative".to_string())
+                }
+            }
+            ["help"] => Ok(ConsoleCommand::Help),
+            [] => Err("empty command".to_string()),
+            _ => Err(format!("unknown command: {line} ({HELP})")),
+        }
+    }
+}
+
+fn number<T: FromStr>(word: &str) -> Result<T, String> {
+    word.parse().map_err(|_| format!("not a number: {word}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_command() {
+        let cases = [
+            (
+                "spawn enemy invader 10 -20.5",
+                ConsoleCommand::SpawnEnemy {
+                    kind: EnemyKind::Invader,
+                    x: 10.,
+                    y: -20.5,
+                },
+            ),
+            ("god on", ConsoleCommand::God(true)),
+            ("  god   off ", ConsoleCommand::God(false)),
+            ("wave 3", ConsoleCommand::Wave(3)),
+            (
+                "give powerup shield",
+                ConsoleCommand::GivePowerUp(PowerUpKind::Shield),
+            ),
+            (
+                "set enemy_max 5",
+                ConsoleCommand::Set {
+                    key: "enemy_max".to_string(),
+                    value: "5".to_string(),
+                },
+            ),
+            ("kill_all", ConsoleCommand::KillAll),
+            ("timescale 0.25", ConsoleCommand::TimeScale(0.25)),
+            ("help", ConsoleCommand::Help),
+        ];
+
+        for (line, expected) in cases {
+            assert_eq!(line.parse::<ConsoleCommand>(), Ok(expected), "{line}");
+        }
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        for line in [
+            "",
+            "dance",
+            "spawn enemy dragon 0 0",
+            "spawn enemy invader x 0",
+            "god maybe",
+            "wave -1",
+            "give powerup cake",
+            "set gravity 1",
+            "timescale -2",
+        ] {
+            assert!(line.parse::<ConsoleCommand>().is_err(), "{line}");
+        }
+    }
+}
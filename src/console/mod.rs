@@ -0,0 +1,273 @@
+use crate::{
+    components::{Enemy, FromEnemy, Player},
+    controls::ControlsSystem,
+    enemy::{formation::FormationMaker, spawn_enemy},
+    events::{EnemyDestroyed, PowerUpCollected},
+    netcode::Online,
+    EnemyCount, GameConfig, GameRng, GameTextures, Players, TimeScale, UiFont, Wave, WaveSpawned,
+    WinSize,
+};
+use bevy::{input::InputSystem, prelude::*, window::ReceivedCharacter};
+
+use self::command::{ConsoleCommand, HELP};
+
+pub mod command;
+
+const CONSOLE_LOG_LINES: usize = 12;
+const CONSOLE_HEIGHT: f32 = 40.; // percent of the window
+
+/// Resource - console state, opened with the backtick key
+#[derive(Default)]
+pub struct ConsoleState {
+    pub open: bool,
+    pub input: String,
+    pub log: Vec<String>,
+    pending: Vec<ConsoleCommand>,
+}
+
+impl ConsoleState {
+    /// Parse `line` and queue it to run on the next update.
+    pub fn submit(&mut self, line: &str) {
+        self.print(format!("> {line}"));
+        match line.parse() {
+            Ok(command) => self.pending.push(command),
+            Err(err) => self.print(err),
+        }
+    }
+
+    pub fn print(&mut self, line: impl Into<String>) {
+        self.log.push(line.into());
+        if self.log.len() > CONSOLE_LOG_LINES {
+            self.log.remove(0);
+        }
+    }
+}
+
+/// Component - console background node
+#[derive(Component)]
+struct ConsoleRoot;
+
+/// Component - console log and prompt text
+#[derive(Component)]
+struct ConsoleText;
+
+/// Plugin - drop-down developer console
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ConsoleState>()
+            .init_resource::<UiFont>()
+            .init_resource::<GameConfig>()
+            .init_resource::<TimeScale>()
+            .init_resource::<Wave>()
+            .init_resource::<WaveSpawned>()
+            .init_resource::<GameRng>()
+            .add_event::<ReceivedCharacter>()
+            .add_event::<EnemyDestroyed>()
+            .add_event::<PowerUpCollected>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                console_input_system
+                    .after(InputSystem)
+                    .before(ControlsSystem),
+            )
+            .add_system(console_execute_system)
+            .add_system(console_ui_system);
+    }
+}
+
+fn console_input_system(
+    mut console: ResMut<ConsoleState>,
+    mut kb: ResMut<Input<KeyCode>>,
+    mut received_characters: EventReader<ReceivedCharacter>,
+    online: Option<Res<Online>>,
+) {
+    let typed: String = received_characters.iter().map(|event| event.char).collect();
+
+    if kb.just_pressed(KeyCode::Grave) {
+        console.open = !console.open;
+        kb.reset(KeyCode::Grave);
+        return;
+    }
+
+    if !console.open {
+        return;
+    }
+
+    console
+        .input
+        .extend(typed.chars().filter(|c| !c.is_control() && *c != '`'));
+
+    if kb.just_pressed(KeyCode::Back) {
+        console.input.pop();
+    }
+    if kb.just_pressed(KeyCode::Return) {
+        let line = std::mem::take(&mut console.input);
+        match online {
+            _ if line.trim().is_empty() => {}
+            // the other peer would never see what they do
+            Some(_) => {
+                console.print(format!("> {line}"));
+                console.print("commands are off in online games");
+            }
+            None => console.submit(&line),
+        }
+    }
+    if kb.just_pressed(KeyCode::Escape) {
+        console.open = false;
+    }
+
+    // swallow the keyboard so typing does not steer or fire
+    kb.reset_all();
+}
+
+fn console_execute_system(
+    mut commands: Commands,
+    mut console: ResMut<ConsoleState>,
+    mut enemy_count: ResMut<EnemyCount>,
+    mut players: ResMut<Players>,
+    mut formation_maker: ResMut<FormationMaker>,
+    mut rng: ResMut<GameRng>,
+    mut config: ResMut<GameConfig>,
+    mut time_scale: ResMut<TimeScale>,
+    (mut wave, mut wave_spawned): (ResMut<Wave>, ResMut<WaveSpawned>),
+    game_textures: Option<Res<GameTextures>>,
+    win_size: Res<WinSize>,
+    mut enemy_destroyed_events: EventWriter<EnemyDestroyed>,
+    mut power_up_events: EventWriter<PowerUpCollected>,
+    enemy_query: Query<(Entity, &Transform), With<Enemy>>,
+    enemy_laser_query: Query<Entity, With<FromEnemy>>,
+    player_query: Query<(Entity, &Player, &Transform)>,
+) {
+    for command in std::mem::take(&mut console.pending) {
+        match command {
+            ConsoleCommand::SpawnEnemy { kind, x, y } => {
+                let game_textures = match &game_textures {
+                    Some(game_textures) => game_textures,
+                    None => {
+                        console.print("sprites are still loading");
+                        continue;
+                    }
+                };
+                let formation = formation_maker
+                    .make(&win_size, &mut rng.rng)
+                    .starting_at((x, y));
+                spawn_enemy(&mut commands, game_textures, kind, formation);
+                enemy_count.0 += 1;
+                console.print(format!("spawned {} at {x} {y}", kind.name()));
+            }
+            ConsoleCommand::God(on) => {
+                for player in players.0.iter_mut() {
+                    player.god = on;
+                }
+                console.print(format!("god mode {}", if on { "on" } else { "off" }));
+            }
+            ConsoleCommand::Wave(n) => {
+                // start the wave from an empty sky
+                for (entity, _) in enemy_query.iter() {
+                    commands.entity(entity).despawn();
+                }
+                for entity in enemy_laser_query.iter() {
+                    commands.entity(entity).despawn();
+                }
+                enemy_count.0 = 0;
+                *formation_maker = FormationMaker::default();
+                wave.0 = n;
+                *wave_spawned = WaveSpawned::default();
+                console.print(format!("wave {n}"));
+            }
+            // to the first player in the game
+            ConsoleCommand::GivePowerUp(kind) => {
+                match player_query.iter().min_by_key(|(_, p, _)| p.0) {
+                    Some((player, _, player_tf)) => {
+                        power_up_events.send(PowerUpCollected {
+                            player,
+                            kind,
+                            position: player_tf.translation,
+                        });
+                        console.print(format!("gave {}", kind.name()));
+                    }
+                    None => console.print("no player to give it to"),
+                }
+            }
+            ConsoleCommand::Set { key, value } => match config.set(&key, &value) {
+                Ok(()) => console.print(format!("{key} = {value}")),
+                Err(err) => console.print(err),
+            },
+            ConsoleCommand::KillAll => {
+                // through EnemyDestroyed so counts and explosions follow
+                for (entity, enemy_tf) in enemy_query.iter() {
+                    commands.entity(entity).despawn();
+                    enemy_destroyed_events.send(EnemyDestroyed {
+                        enemy: entity,
+                        position: enemy_tf.translation,
+                        by_player: None,
+                    });
+                }
+                for entity in enemy_laser_query.iter() {
+                    commands.entity(entity).despawn();
+                }
+                console.print(format!("killed {}", enemy_query.iter().count()));
+            }
+            ConsoleCommand::TimeScale(scale) => {
+                time_scale.0 = scale;
+                console.print(format!("timescale {scale}"));
+            }
+            ConsoleCommand::Help => console.print(HELP),
+        }
+    }
+}
+
+fn console_ui_system(
+    mut commands: Commands,
+    console: Res<ConsoleState>,
+    font: Res<UiFont>,
+    root_query: Query<Entity, With<ConsoleRoot>>,
+    mut text_query: Query<&mut Text, With<ConsoleText>>,
+) {
+    match (console.open, root_query.get_single()) {
+        (true, Err(_)) => {
+            commands
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.), Val::Percent(CONSOLE_HEIGHT)),
+                        position_type: PositionType::Absolute,
+                        position: UiRect {
+                            left: Val::Px(0.),
+                            top: Val::Px(0.),
+                            ..Default::default()
+                        },
+                        align_items: AlignItems::FlexEnd,
+                        padding: UiRect::all(Val::Px(8.)),
+                        ..Default::default()
+                    },
+                    color: Color::rgba(0., 0., 0., 0.8).into(),
+                    ..Default::default()
+                })
+                .insert(ConsoleRoot)
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: font.0.clone(),
+                                font_size: 14.,
+                                color: Color::rgb(0.8, 1., 0.8),
+                            },
+                        ))
+                        .insert(ConsoleText);
+                });
+        }
+        (false, Ok(root)) => commands.entity(root).despawn_recursive(),
+        _ => {}
+    }
+
+    if console.open {
+        for mut text in text_query.iter_mut() {
+            let mut lines = console.log.clone();
+            lines.push(format!("> {}_", console.input));
+            text.sections[0].value = lines.join("\n");
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use bevy::prelude::*;
+
+use crate::{
+    gamepad::PlayerGamepads,
+    settings::{Settings, SettingsFile},
+    sound::AudioSettings,
+    UiFont, MAX_PLAYERS,
+};
+
+use super::{Action, Binding};
+
+const MENU_KEY: KeyCode = KeyCode::F1;
+/// rows after the actions, adjusted with Left/Right
+const VOLUMES: [&str; 3] = ["Master", "Effects", "Music"];
+const VOLUME_STEP: f32 = 0.1;
+/// rows after the volumes, switched on and off with Left/Right or Enter
+const EFFECTS: usize = 3;
+
+/// Resource - the controls, volume and effect settings screen, opened with F1
+#[derive(Default)]
+pub struct SettingsMenu {
+    pub open: bool,
+    /// player whose controls are shown
+    pub player: usize,
+    /// index into `Action::ALL`, then into the volumes, then the effects
+    pub selected: usize,
+    /// waiting for the key or button to bind to the selected action
+    pub listening: bool,
+}
+
+/// Component - settings menu background node
+#[derive(Component)]
+struct SettingsMenuRoot;
+
+/// Component - settings menu text
+#[derive(Component)]
+struct SettingsMenuText;
+
+/// Plugin - settings screen for rebinding actions, setting volumes and
+/// switching camera effects
+pub struct SettingsMenuPlugin;
+
+impl Plugin for SettingsMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SettingsMenu>()
+            .init_resource::<UiFont>()
+            .add_system(settings_menu_input_system)
+            .add_system(settings_menu_ui_system.after(settings_menu_input_system));
+    }
+}
+
+fn settings_menu_input_system(
+    kb: Res<Input<KeyCode>>,
+    gamepads: Res<PlayerGamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    settings_file: Res<SettingsFile>,
+    mut settings: ResMut<Settings>,
+    mut menu: ResMut<SettingsMenu>,
+) {
+    if menu.listening {
+        let binding = match (
+            kb.get_just_pressed().next(),
+            gamepads.just_pressed_button(menu.player, &gamepad_buttons),
+        ) {
+            (Some(KeyCode::Escape), _) => {
+                menu.listening = false;
+                return;
+            }
+            (Some(key), _) => Binding::Key(*key),
+            (None, Some(button)) => Binding::Button(button),
+            (None, None) => return,
+        };
+
+        let action = Action::ALL[menu.selected];
+        settings.rebind(menu.player, action, binding);
+        if let Err(err) = settings.save(&settings_file) {
+            warn!("{err}");
+        }
+        menu.listening = false;
+        return;
+    }
+
+    if kb.just_pressed(MENU_KEY) || (menu.open && kb.just_pressed(KeyCode::Escape)) {
+        menu.open = !menu.open;
+        menu.listening = false;
+        return;
+    }
+
+    if !menu.open {
+        return;
+    }
+
+    let count = Action::ALL.len() + VOLUMES.len() + EFFECTS;
+    if kb.just_pressed(KeyCode::Up) {
+        menu.selected = (menu.selected + count - 1) % count;
+    }
+    if kb.just_pressed(KeyCode::Down) {
+        menu.selected = (menu.selected + 1) % count;
+    }
+
+    let volume_row = menu.selected.checked_sub(Action::ALL.len());
+    let effect_row = volume_row.and_then(|row| row.checked_sub(VOLUMES.len()));
+    if let Some(row) = effect_row {
+        if kb.any_just_pressed([KeyCode::Left, KeyCode::Right, KeyCode::Return]) {
+            if let Some((_, on)) = settings.effects.toggles().into_iter().nth(row) {
+                *on = !*on;
+            }
+            if let Err(err) = settings.save(&settings_file) {
+                warn!("{err}");
+            }
+        }
+        return;
+    }
+
+    let step = match (
+        kb.just_pressed(KeyCode::Left),
+        kb.just_pressed(KeyCode::Right),
+    ) {
+        (true, false) => -VOLUME_STEP,
+        (false, true) => VOLUME_STEP,
+        _ => 0.,
+    };
+    match volume_row {
+        Some(row) if step != 0. => {
+            let level = volume(&mut settings.audio, row);
+            *level = ((*level + step) * 10.).round().clamp(0., 10.) / 10.;
+            if let Err(err) = settings.save(&settings_file) {
+                warn!("{err}");
+            }
+        }
+        Some(_) => {}
+        None => {
+            if kb.just_pressed(KeyCode::Left) || kb.just_pressed(KeyCode::Right) {
+                menu.player = (menu.player + 1) % MAX_PLAYERS;
+            }
+            if kb.just_pressed(KeyCode::Return) {
+                menu.listening = true;
+            }
+        }
+    }
+}
+
+/// The volume on row `row` of `VOLUMES`.
+fn volume(audio: &mut AudioSettings, row: usize) -> &mut f32 {
+    match row {
+        0 => &mut audio.master,
+        1 => &mut audio.sfx,
+        _ => &mut audio.music,
+    }
+}
+
+fn settings_menu_ui_system(
+    mut commands: Commands,
+    menu: Res<SettingsMenu>,
+    settings: Res<Settings>,
+    font: Res<UiFont>,
+    root_query: Query<Entity, With<SettingsMenuRoot>>,
+    mut text_query: Query<&mut Text, With<SettingsMenuText>>,
+) {
+    match (menu.open, root_query.get_single()) {
+        (true, Err(_)) => {
+            commands
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                        position_type: PositionType::Absolute,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    color: Color::rgba(0., 0., 0., 0.85).into(),
+                    ..Default::default()
+                })
+                .insert(SettingsMenuRoot)
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: font.0.clone(),
+                                font_size: 18.,
+                                color: Color::WHITE,
+                            },
+                        ))
+                        .insert(SettingsMenuText);
+                });
+        }
+        (false, Ok(root)) => commands.entity(root).despawn_recursive(),
+        _ => {}
+    }
+
+    if !menu.open {
+        return;
+    }
+
+    let mut lines = vec![
+        format!("CONTROLS - PLAYER {}", menu.player + 1),
+        String::new(),
+    ];
+    for (i, action) in Action::ALL.iter().enumerate() {
+        let bound: Vec<_> = settings.bindings[menu.player]
+            .bindings(*action)
+            .iter()
+            .map(|binding| match binding {
+                Binding::Key(key) => format!("{key:?}"),
+                Binding::Button(button) => format!("Pad {button:?}"),
+            })
+            .collect();
+        let marker = if i == menu.selected { ">" } else { " " };
+        let bound = if i == menu.selected && menu.listening {
+            "press a key or button...".to_string()
+        } else {
+            bound.join(", ")
+        };
+        lines.push(format!("{marker} {:<12} {bound}", action.name()));
+    }
+    lines.push(String::new());
+    let audio = &settings.audio;
+    let levels = [audio.master, audio.sfx, audio.music];
+    for (row, (name, level)) in VOLUMES.iter().zip(levels).enumerate() {
+        let marker = if Action::ALL.len() + row == menu.selected {
+            ">"
+        } else {
+            " "
+        };
+        lines.push(format!(
+            "{marker} {name:<12} {:>3}%",
+            (level * 100.).round()
+        ));
+    }
+    if audio.muted {
+        lines.push("  muted, M to unmute".to_string());
+    }
+    lines.push(String::new());
+    let mut effects = settings.effects.clone();
+    for (row, (name, on)) in effects.toggles().into_iter().enumerate() {
+        let marker = if Action::ALL.len() + VOLUMES.len() + row == menu.selected {
+            ">"
+        } else {
+            " "
+        };
+        let on = if *on { "on" } else { "off" };
+        lines.push(format!("{marker} {name:<12} {on}"));
+    }
+    lines.push(String::new());
+    lines.push(
+        "Up/Down select, Left/Right player, volume or effect, Enter rebind, Esc close".to_string(),
+    );
+
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = lines.join("\n");
+    }
+}
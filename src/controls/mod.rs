@@ -0,0 +1,399 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use bevy::{input::InputSystem, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    gamepad::PlayerGamepads,
+    settings::{Settings, SettingsFile},
+    Movement, Paused, Screen, TimeScale, MAX_PLAYERS,
+};
+
+use self::menu::{SettingsMenu, SettingsMenuPlugin};
+
+pub mod menu;
+
+/// What the player wants to do, whatever device it came from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    Fire,
+    Bomb,
+    Pause,
+}
+
+impl Action {
+    pub const ALL: [Action; 7] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::Fire,
+        Action::Bomb,
+        Action::Pause,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::MoveUp => "Move up",
+            Action::MoveDown => "Move down",
+            Action::Fire => "Fire",
+            Action::Bomb => "Bomb",
+            Action::Pause => "Pause",
+        }
+    }
+
+    fn bit(&self) -> u8 {
+        1 << *self as u8
+    }
+}
+
+/// A key or gamepad button bound to an action
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Button(GamepadButtonType),
+}
+
+impl Binding {
+    fn same_device(&self, other: &Binding) -> bool {
+        matches!(
+            (self, other),
+            (Binding::Key(_), Binding::Key(_)) | (Binding::Button(_), Binding::Button(_))
+        )
+    }
+}
+
+/// Action to bindings map of one player, part of the user `Settings`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct InputBindings(BTreeMap<Action, Vec<Binding>>);
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        Self::for_player(0)
+    }
+}
+
+impl InputBindings {
+    /// Default scheme of player `number`: arrows for player one, WASD for
+    /// player two, and each player's own gamepad.
+    pub fn for_player(number: usize) -> Self {
+        use self::Binding::{Button, Key};
+        use bevy::prelude::GamepadButtonType::*;
+
+        let keys = if number == 0 {
+            [
+                KeyCode::Left,
+                KeyCode::Right,
+                KeyCode::Up,
+                KeyCode::Down,
+                KeyCode::Space,
+                KeyCode::B,
+                KeyCode::P,
+            ]
+        } else {
+            [
+                KeyCode::A,
+                KeyCode::D,
+                KeyCode::W,
+                KeyCode::S,
+                KeyCode::LShift,
+                KeyCode::Q,
+                KeyCode::Tab,
+            ]
+        };
+        let buttons: [&[GamepadButtonType]; 7] = [
+            &[DPadLeft],
+            &[DPadRight],
+            &[DPadUp],
+            &[DPadDown],
+            &[South, East, North, West],
+            &[RightTrigger],
+            &[Start],
+        ];
+
+        Self(
+            Action::ALL
+                .iter()
+                .zip(keys)
+                .zip(buttons)
+                .map(|((action, key), buttons)| {
+                    let mut bindings = vec![Key(key)];
+                    bindings.extend(buttons.iter().map(|button| Button(*button)));
+                    (*action, bindings)
+                })
+                .collect(),
+        )
+    }
+
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.0.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Bind `binding` to `action` in place of its first binding on the same
+    /// device, taking it away from any other action.
+    pub fn rebind(&mut self, action: Action, binding: Binding) {
+        self.unbind(binding);
+
+        let bindings = self.0.entry(action).or_default();
+        match bindings
+            .iter_mut()
+            .find(|bound| bound.same_device(&binding))
+        {
+            Some(bound) => *bound = binding,
+            None => bindings.push(binding),
+        }
+    }
+
+    pub fn unbind(&mut self, binding: Binding) {
+        for bindings in self.0.values_mut() {
+            bindings.retain(|bound| *bound != binding);
+        }
+    }
+
+    /// Actions player `number` holds on the keyboard and their gamepad.
+    pub fn read(
+        &self,
+        number: usize,
+        kb: &Input<KeyCode>,
+        gamepads: &PlayerGamepads,
+        gamepad_buttons: &Input<GamepadButton>,
+        gamepad_axes: &Axis<GamepadAxis>,
+    ) -> ActionFrame {
+        let mut held = ActionSet::default();
+        for (action, bindings) in self.0.iter() {
+            let pressed = bindings.iter().any(|binding| match binding {
+                Binding::Key(key) => kb.pressed(*key),
+                Binding::Button(button) => gamepads.pressed(number, gamepad_buttons, *button),
+            });
+            if pressed {
+                held.insert(*action);
+            }
+        }
+
+        ActionFrame {
+            held,
+            stick: gamepads.stick(number, gamepad_axes),
+        }
+    }
+}
+
+/// Set of held actions, one bit each
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActionSet(pub u8);
+
+impl ActionSet {
+    pub fn insert(&mut self, action: Action) {
+        self.0 |= action.bit();
+    }
+
+    pub fn contains(&self, action: Action) -> bool {
+        self.0 & action.bit() != 0
+    }
+}
+
+impl FromIterator<Action> for ActionSet {
+    fn from_iter<I: IntoIterator<Item = Action>>(actions: I) -> Self {
+        let mut set = ActionSet::default();
+        for action in actions {
+            set.insert(action);
+        }
+        set
+    }
+}
+
+/// One frame of input: held actions plus the analog stick
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ActionFrame {
+    pub held: ActionSet,
+    pub stick: Vec2,
+}
+
+/// One player's actions this frame, read by gameplay instead of raw input
+#[derive(Clone, Default)]
+pub struct ActionState {
+    current: ActionFrame,
+    previous: ActionSet,
+}
+
+impl ActionState {
+    pub fn update(&mut self, frame: ActionFrame) {
+        self.previous = self.current.held;
+        self.current = frame;
+    }
+
+    pub fn frame(&self) -> ActionFrame {
+        self.current
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.current.held.contains(action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.pressed(action) && !self.previous.contains(action)
+    }
+
+    /// Movement direction, each axis in -1..=1 with +y up. Held directions
+    /// win over the stick, which steers proportionally.
+    pub fn movement(&self) -> Vec2 {
+        let axis =
+            |negative, positive, stick: f32| match (self.pressed(negative), self.pressed(positive))
+            {
+                (true, false) => -1.,
+                (false, true) => 1.,
+                _ => stick,
+            };
+
+        Vec2::new(
+            axis(Action::MoveLeft, Action::MoveRight, self.current.stick.x),
+            axis(Action::MoveDown, Action::MoveUp, self.current.stick.y),
+        )
+    }
+}
+
+/// Resource - actions of each player, indexed by `Player` number
+#[derive(Clone, Default)]
+pub struct PlayerActions(pub [ActionState; MAX_PLAYERS]);
+
+/// Where a player's `ActionState` comes from
+#[derive(Default)]
+pub enum InputSource {
+    /// keyboard and gamepad, through the `Settings` bindings
+    #[default]
+    Devices,
+    /// recorded frames, one per update, then nothing held
+    Replay(VecDeque<ActionFrame>),
+}
+
+/// Resource - input source of each player, indexed by `Player` number
+#[derive(Default)]
+pub struct InputSources(pub [InputSource; MAX_PLAYERS]);
+
+/// Label - `PlayerActions` are up to date after this
+#[derive(Clone, Debug, PartialEq, Eq, Hash, SystemLabel)]
+pub struct ControlsSystem;
+
+/// Plugin - actions from rebindable keys, gamepad or replays, and the settings menu
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SettingsFile>();
+        let settings = Settings::load_or_default(app.world.resource::<SettingsFile>());
+
+        app.insert_resource(settings)
+            .init_resource::<PlayerActions>()
+            .init_resource::<InputSources>()
+            .init_resource::<PlayerGamepads>()
+            .init_resource::<Input<GamepadButton>>()
+            .init_resource::<Axis<GamepadAxis>>()
+            .init_resource::<Paused>()
+            .init_resource::<TimeScale>()
+            .init_resource::<Screen>()
+            .add_plugin(SettingsMenuPlugin)
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                controls_system.label(ControlsSystem).after(InputSystem),
+            )
+            // so the whole tick moves at the same scale
+            .add_system(pause_system.before(Movement));
+    }
+}
+
+fn controls_system(
+    kb: Res<Input<KeyCode>>,
+    gamepads: Res<PlayerGamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    settings: Res<Settings>,
+    menu: Res<SettingsMenu>,
+    mut sources: ResMut<InputSources>,
+    mut actions: ResMut<PlayerActions>,
+) {
+    for (number, (source, actions)) in sources.0.iter_mut().zip(actions.0.iter_mut()).enumerate() {
+        let frame = match source {
+            // the menu has the devices to itself
+            InputSource::Devices if menu.open => ActionFrame::default(),
+            InputSource::Devices => settings.bindings[number].read(
+                number,
+                &kb,
+                &gamepads,
+                &gamepad_buttons,
+                &gamepad_axes,
+            ),
+            InputSource::Replay(frames) => frames.pop_front().unwrap_or_default(),
+        };
+        actions.update(frame);
+    }
+}
+
+fn pause_system(
+    actions: Res<PlayerActions>,
+    screen: Res<Screen>,
+    mut paused: ResMut<Paused>,
+    mut time_scale: ResMut<TimeScale>,
+) {
+    // the other screens keep the game paused themselves
+    if *screen != Screen::Playing {
+        return;
+    }
+
+    // either player can pause
+    if actions
+        .0
+        .iter()
+        .any(|actions| actions.just_pressed(Action::Pause))
+    {
+        paused.toggle(&mut time_scale);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebinding_replaces_the_same_device_and_steals_from_others() {
+        let mut bindings = InputBindings::default();
+
+        bindings.rebind(Action::Fire, Binding::Key(KeyCode::Left));
+
+        assert_eq!(
+            bindings.bindings(Action::Fire)[0],
+            Binding::Key(KeyCode::Left)
+        );
+        assert!(bindings
+            .bindings(Action::Fire)
+            .contains(&Binding::Button(GamepadButtonType::South)));
+        assert!(!bindings
+            .bindings(Action::MoveLeft)
+            .contains(&Binding::Key(KeyCode::Left)));
+    }
+
+    #[test]
+    fn held_directions_win_over_the_stick() {
+        let mut actions = ActionState::default();
+        actions.update(ActionFrame {
+            held: ActionSet::default(),
+            stick: Vec2::new(0.4, -0.2),
+        });
+        assert_eq!(actions.movement(), Vec2::new(0.4, -0.2));
+
+        actions.update(ActionFrame {
+            held: [Action::MoveLeft].into_iter().collect(),
+            stick: Vec2::new(0.4, -0.2),
+        });
+        assert_eq!(actions.movement(), Vec2::new(-1., -0.2));
+        assert!(actions.just_pressed(Action::MoveLeft));
+
+        actions.update(actions.frame());
+        assert!(!actions.just_pressed(Action::MoveLeft));
+    }
+}
@@ -0,0 +1,223 @@
+use std::f32::consts::TAU;
+
+use crate::{
+    components::{Enemy, Explosion, Laser, SpriteSize, Velocity},
+    enemy::formation::Formation,
+    particles::{Particle, MAX_PARTICLES},
+    EnemyCount, UiFont, BASE_SPEED,
+};
+use bevy::{
+    diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin},
+    math::Vec3Swizzles,
+    prelude::*,
+};
+
+const DEBUG_Z: f32 = 100.;
+const LINE_WIDTH: f32 = 1.5;
+const ELLIPSE_SEGMENTS: usize = 32;
+// velocity arrows show where the entity will be in this many seconds
+const ARROW_SECONDS: f32 = 0.1;
+
+const HITBOX_COLOR: Color = Color::rgba(0., 1., 0., 0.8);
+const VELOCITY_COLOR: Color = Color::rgba(1., 1., 0., 0.8);
+const FORMATION_COLOR: Color = Color::rgba(0., 0.6, 1., 0.6);
+
+/// Resource - debug overlay state, toggled with F3
+#[derive(Default)]
+pub struct DebugOverlay {
+    pub enabled: bool,
+}
+
+/// Component - anything drawn by the overlay, redrawn every frame
+#[derive(Component)]
+pub struct DebugShape;
+
+/// Component - the stats panel text
+#[derive(Component)]
+pub struct DebugPanel;
+
+/// Plugin - F3 overlay with hitboxes, velocities, formations and entity counts
+pub struct DebugPlugin;
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DebugOverlay>()
+            .init_resource::<Diagnostics>()
+            .add_plugin(FrameTimeDiagnosticsPlugin)
+            .init_resource::<UiFont>()
+            .add_system(debug_toggle_system)
+            .add_system_to_stage(CoreStage::PostUpdate, debug_draw_system)
+            .add_system_to_stage(CoreStage::PostUpdate, debug_panel_system);
+    }
+}
+
+fn debug_toggle_system(
+    mut commands: Commands,
+    kb: Res<Input<KeyCode>>,
+    mut overlay: ResMut<DebugOverlay>,
+    font: Res<UiFont>,
+    panel_query: Query<Entity, With<DebugPanel>>,
+) {
+    if !kb.just_pressed(KeyCode::F3) {
+        return;
+    }
+
+    overlay.enabled = !overlay.enabled;
+
+    if overlay.enabled {
+        commands
+            .spawn_bundle(
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: font.0.clone(),
+                        font_size: 16.,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_style(Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        left: Val::Px(8.),
+                        top: Val::Px(8.),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                }),
+            )
+            .insert(DebugPanel);
+    } else {
+        for entity in panel_query.iter() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn debug_draw_system(
+    mut commands: Commands,
+    overlay: Res<DebugOverlay>,
+    shape_query: Query<Entity, With<DebugShape>>,
+    hitbox_query: Query<(&Transform, &SpriteSize)>,
+    velocity_query: Query<(&Transform, &Velocity)>,
+    formation_query: Query<&Formation>,
+) {
+    // immediate mode: throw last frame's lines away
+    for entity in shape_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    if !overlay.enabled {
+        return;
+    }
+
+    for (tf, size) in hitbox_query.iter() {
+        let half = size.0 * tf.scale.xy() / 2.;
+        let center = tf.translation.xy();
+        let corners = [
+            center + Vec2::new(-half.x, -half.y),
+            center + Vec2::new(half.x, -half.y),
+            center + Vec2::new(half.x, half.y),
+            center + Vec2::new(-half.x, half.y),
+        ];
+        for i in 0..corners.len() {
+            spawn_line(&mut commands, corners[i], corners[(i + 1) % 4], HITBOX_COLOR);
+        }
+    }
+
+    for (tf, velocity) in velocity_query.iter() {
+        let from = tf.translation.xy();
+        let offset = Vec2::new(velocity.x, velocity.y) * BASE_SPEED * ARROW_SECONDS;
+        if offset == Vec2::ZERO {
+            continue;
+        }
+        let to = from + offset;
+        spawn_line(&mut commands, from, to, VELOCITY_COLOR);
+
+        // arrow head
+        let back = -offset.normalize() * 8.;
+        for angle in [0.5_f32, -0.5] {
+            let head = Vec2::from_angle(angle).rotate(back);
+            spawn_line(&mut commands, to, to + head, VELOCITY_COLOR);
+        }
+    }
+
+    // formation members share an ellipse, draw each one once
+    let mut drawn: Vec<((f32, f32), (f32, f32))> = Vec::new();
+    for formation in formation_query.iter() {
+        let key = (formation.pivot, formation.radius);
+        if drawn.contains(&key) {
+            continue;
+        }
+        drawn.push(key);
+
+        let pivot = Vec2::new(formation.pivot.0, formation.pivot.1);
+        let radius = Vec2::new(formation.radius.0, formation.radius.1);
+        let point = |i: usize| {
+            let angle = TAU * i as f32 / ELLIPSE_SEGMENTS as f32;
+            pivot + radius * Vec2::new(angle.cos(), angle.sin())
+        };
+        for i in 0..ELLIPSE_SEGMENTS {
+            spawn_line(&mut commands, point(i), point(i + 1), FORMATION_COLOR);
+        }
+
+        // pivot cross
+        for offset in [Vec2::new(6., 6.), Vec2::new(6., -6.)] {
+            spawn_line(&mut commands, pivot - offset, pivot + offset, FORMATION_COLOR);
+        }
+    }
+}
+
+fn spawn_line(commands: &mut Commands, from: Vec2, to: Vec2, color: Color) {
+    let delta = to - from;
+    let length = delta.length();
+    if length == 0. {
+        return;
+    }
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color,
+                custom_size: Some(Vec2::new(length, LINE_WIDTH)),
+                ..Default::default()
+            },
+            transform: Transform {
+                translation: ((from + to) / 2.).extend(DEBUG_Z),
+                rotation: Quat::from_rotation_z(delta.y.atan2(delta.x)),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(DebugShape);
+}
+
+fn debug_panel_system(
+    overlay: Res<DebugOverlay>,
+    enemy_count: Res<EnemyCount>,
+    diagnostics: Res<Diagnostics>,
+    enemy_query: Query<(), With<Enemy>>,
+    laser_query: Query<(), With<Laser>>,
+    explosion_query: Query<(), With<Explosion>>,
+    particle_query: Query<(), With<Particle>>,
+    mut panel_query: Query<&mut Text, With<DebugPanel>>,
+) {
+    if !overlay.enabled {
+        return;
+    }
+
+    let fps = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.average())
+        .unwrap_or(0.);
+
+    for mut text in panel_query.iter_mut() {
+        text.sections[0].value = format!(
+            "FPS: {fps:.0}\nEnemies: {}\nLasers: {}\nExplosions: {}\nParticles: {}/{MAX_PARTICLES}\nEnemyCount: {}",
+            enemy_query.iter().count(),
+            laser_query.iter().count(),
+            explosion_query.iter().count(),
+            particle_query.iter().count(),
+            enemy_count.0,
+        );
+    }
+}
@@ -0,0 +1,272 @@
+use bevy::{
+    prelude::*,
+    render::camera::ScalingMode,
+    window::{WindowId, WindowMode, WindowResized},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    controls::menu::SettingsMenu,
+    settings::{Settings, SettingsFile},
+    WinSize, WINDOW_HEIGHT, WINDOW_WIDTH,
+};
+
+const FULLSCREEN_KEY: KeyCode = KeyCode::F11;
+const BORDERLESS_KEY: KeyCode = KeyCode::F10;
+/// in front of everything in the world, the UI is drawn after it
+const LETTERBOX_Z: f32 = 900.;
+/// how far the bars reach past the edges of the view, so screen shake
+/// doesn't uncover the world behind them
+const LETTERBOX_OVERLAP: f32 = 32.;
+
+/// Window options, part of the user `Settings`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplaySettings {
+    /// toggled with F11
+    pub fullscreen: bool,
+    /// no title bar or frame on the window, toggled with F10
+    pub borderless: bool,
+}
+
+impl DisplaySettings {
+    pub fn mode(&self) -> WindowMode {
+        if self.fullscreen {
+            WindowMode::BorderlessFullscreen
+        } else {
+            WindowMode::Windowed
+        }
+    }
+}
+
+/// Resource - the window's logical size and the scale the playfield
+/// (`WinSize`) is drawn at to fit in it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Letterbox {
+    pub window: Vec2,
+    pub scale: f32,
+}
+
+impl Default for Letterbox {
+    fn default() -> Self {
+        let window = Vec2::new(WINDOW_WIDTH as f32, WINDOW_HEIGHT as f32);
+        Self::fit(window, window)
+    }
+}
+
+impl Letterbox {
+    /// Largest scale of `playfield` that fits in `window` without changing
+    /// shape. A window under a pixel, like a minimized one, counts as one
+    /// pixel so the scale and view stay finite.
+    pub fn fit(window: Vec2, playfield: Vec2) -> Self {
+        let window = window.max(Vec2::ONE);
+        let scale = (window / playfield.max(Vec2::ONE)).min_element();
+        Self { window, scale }
+    }
+
+    /// Width of the bars on the left and right, and height of the bars on
+    /// the top and bottom, in window pixels.
+    pub fn bars(&self, playfield: Vec2) -> Vec2 {
+        ((self.window - playfield * self.scale) / 2.).max(Vec2::ZERO)
+    }
+
+    /// Size of the world the camera shows, the playfield and its bars.
+    pub fn view(&self) -> Vec2 {
+        self.window / self.scale
+    }
+
+    /// Show the world at the letterbox scale, centred on the playfield.
+    pub fn apply(&self, projection: &mut OrthographicProjection) {
+        let half = self.window / 2.;
+        projection.scaling_mode = ScalingMode::None;
+        projection.left = -half.x;
+        projection.right = half.x;
+        projection.bottom = -half.y;
+        projection.top = half.y;
+        projection.scale = 1. / self.scale;
+    }
+}
+
+/// Component - one of the bars hiding the world around the playfield
+#[derive(Component)]
+pub struct LetterboxBar;
+
+/// Plugin - fits the playfield to the window with letterboxing, and
+/// the fullscreen and borderless toggles
+pub struct DisplayPlugin;
+
+impl Plugin for DisplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SettingsFile>()
+            .init_resource::<Settings>()
+            .init_resource::<Letterbox>()
+            .add_event::<WindowResized>()
+            .add_system(letterbox_system)
+            .add_system(letterbox_camera_system.after(letterbox_system))
+            .add_system(letterbox_bar_system.after(letterbox_system))
+            .add_system(display_toggle_system)
+            .add_system(display_settings_system.after(display_toggle_system));
+    }
+}
+
+/// Follow the primary window's size. The `WinSize` playfield stays put, so
+/// spawns and bounds don't move with the window.
+fn letterbox_system(
+    win_size: Option<ResMut<WinSize>>,
+    mut letterbox: ResMut<Letterbox>,
+    mut resized_events: EventReader<WindowResized>,
+) {
+    let mut win_size = match win_size {
+        Some(win_size) => win_size,
+        None => return,
+    };
+
+    // a minimized window reports 0x0, keep the last real size until it's back
+    let window = match resized_events
+        .iter()
+        .filter(|event| event.width > 0. && event.height > 0.)
+        .rfind(|event| event.id == WindowId::primary())
+    {
+        Some(event) => Vec2::new(event.width, event.height),
+        None => return,
+    };
+    win_size.window = window;
+    *letterbox = Letterbox::fit(window, Vec2::new(win_size.w, win_size.h));
+}
+
+/// Point the 2d camera at the playfield, on start and when the window changes.
+fn letterbox_camera_system(
+    letterbox: Res<Letterbox>,
+    mut query: Query<&mut OrthographicProjection, With<Camera2d>>,
+) {
+    for mut projection in query.iter_mut() {
+        if letterbox.is_changed() || projection.is_added() {
+            letterbox.apply(&mut projection);
+        }
+    }
+}
+
+/// Frame the playfield with bars in the clear colour, filling the rest of
+/// the view, again when the playfield or the window changes size.
+fn letterbox_bar_system(
+    mut commands: Commands,
+    win_size: Option<Res<WinSize>>,
+    letterbox: Res<Letterbox>,
+    clear_color: Option<Res<ClearColor>>,
+    query: Query<Entity, With<LetterboxBar>>,
+) {
+    let win_size = match win_size {
+        Some(win_size) if win_size.is_changed() || letterbox.is_changed() => win_size,
+        _ => return,
+    };
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let color = clear_color.map_or(Color::BLACK, |clear_color| clear_color.0);
+    let playfield = Vec2::new(win_size.w, win_size.h);
+    // in world units, past the edges of the view
+    let bar = letterbox.bars(playfield) / letterbox.scale + LETTERBOX_OVERLAP;
+    let offset = (playfield + bar) / 2.;
+    let view_height = playfield.y + 2. * bar.y;
+    let bars = [
+        (Vec2::new(-offset.x, 0.), Vec2::new(bar.x, view_height)),
+        (Vec2::new(offset.x, 0.), Vec2::new(bar.x, view_height)),
+        (Vec2::new(0., offset.y), Vec2::new(playfield.x, bar.y)),
+        (Vec2::new(0., -offset.y), Vec2::new(playfield.x, bar.y)),
+    ];
+    for (position, size) in bars {
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(size),
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(position.extend(LETTERBOX_Z)),
+                ..Default::default()
+            })
+            .insert(LetterboxBar);
+    }
+}
+
+/// F11 toggles fullscreen and F10 the window frame, unless a binding is
+/// being listened for.
+fn display_toggle_system(
+    kb: Res<Input<KeyCode>>,
+    menu: Option<Res<SettingsMenu>>,
+    settings_file: Res<SettingsFile>,
+    mut settings: ResMut<Settings>,
+) {
+    if menu.is_some_and(|menu| menu.listening) {
+        return;
+    }
+
+    let display = &mut settings.display;
+    if kb.just_pressed(FULLSCREEN_KEY) {
+        display.fullscreen = !display.fullscreen;
+    } else if kb.just_pressed(BORDERLESS_KEY) {
+        display.borderless = !display.borderless;
+    } else {
+        return;
+    }
+    if let Err(err) = settings.save(&settings_file) {
+        warn!("{err}");
+    }
+}
+
+/// Put the window in the mode of the settings, on start and when they change.
+fn display_settings_system(settings: Res<Settings>, windows: Option<ResMut<Windows>>) {
+    let mut windows = match windows {
+        Some(windows) if settings.is_changed() => windows,
+        _ => return,
+    };
+    let window = match windows.get_primary_mut() {
+        Some(window) => window,
+        None => return,
+    };
+
+    let display = &settings.display;
+    if window.mode() != display.mode() {
+        window.set_mode(display.mode());
+    }
+    if window.decorations() == display.borderless {
+        window.set_decorations(!display.borderless);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fits_the_playfield_with_bars_on_the_long_side() {
+        let playfield = Vec2::new(800., 720.);
+
+        let letterbox = Letterbox::fit(Vec2::new(1920., 1080.), playfield);
+        assert_eq!(letterbox.scale, 1.5);
+        assert_eq!(letterbox.bars(playfield), Vec2::new(360., 0.));
+        assert_eq!(letterbox.view(), Vec2::new(1280., 720.));
+
+        let letterbox = Letterbox::fit(Vec2::new(400., 720.), playfield);
+        assert_eq!(letterbox.scale, 0.5);
+        assert_eq!(letterbox.bars(playfield), Vec2::new(0., 180.));
+
+        assert_eq!(Letterbox::fit(playfield, playfield).scale, 1.);
+    }
+
+    #[test]
+    fn minimized_window_keeps_the_scale_finite() {
+        let playfield = Vec2::new(800., 720.);
+        let letterbox = Letterbox::fit(Vec2::ZERO, playfield);
+
+        assert!(letterbox.scale > 0.);
+        assert!(letterbox.bars(playfield).is_finite());
+        assert!(letterbox.view().is_finite());
+
+        let mut projection = OrthographicProjection::default();
+        letterbox.apply(&mut projection);
+        assert!(projection.scale.is_finite());
+        assert!(projection.right > projection.left);
+    }
+}
@@ -1,5 +1,5 @@
 use bevy::prelude::Component;
-use rand::{thread_rng, Rng};
+use rand::Rng;
 
 use crate::{WinSize, FORMATION_MEMBERS_MAX, BASE_SPEED};
 
@@ -13,16 +13,25 @@ pub struct Formation {
     pub angle: f32,
 }
 
+impl Formation {
+    /// Same ellipse, entered from `start` instead.
+    pub fn starting_at(mut self, start: (f32, f32)) -> Self {
+        self.start = start;
+        self.angle = (start.1 - self.pivot.1).atan2(start.0 - self.pivot.0);
+        self
+    }
+}
+
 
 /// Resource - Formation Maker
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct FormationMaker {
     current_template: Option<Formation>,
     current_members: u32,
 }
 
 impl FormationMaker {
-    pub fn make(&mut self, win_size: &WinSize) -> Formation {
+    pub fn make(&mut self, win_size: &WinSize, rng: &mut impl Rng) -> Formation {
         match (&self.current_template, self.current_members >= FORMATION_MEMBERS_MAX){
             // if has current tmpl and still within max mems
             (Some(tmpl), false) => {
@@ -31,13 +40,11 @@ impl FormationMaker {
             },
             // if first formation or previous formation is full (need to create a new one)
             (None, _) | (_, true) => {
-                let mut rng = thread_rng();
-                
                 // compute the start x/y
                 let w_span = win_size.w / 2. + 100.;
                 let h_span = win_size.h / 2. + 100.;
                 let x = if rng.gen_bool(0.5) {w_span} else {-w_span};
-                let y = rng.gen_range(-h_span..h_span) as f32;
+                let y = rng.gen_range(-h_span..h_span);
                 let start = (x, y);
                 
                 // compute the pivots x/y
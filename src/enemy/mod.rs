@@ -1,37 +1,97 @@
+use std::f64::consts::PI;
+
 use crate::{
-    components::{Enemy, SpriteSize, Laser, Movable, FromEnemy, Velocity},
-    EnemyCount, GameTextures, WinSize, ENEMY_MAX, ENEMY_SIZE, SPRITE_SCALE, ENEMY_LASER_SIZE, enemy_movement_system,
+    collision::{shape::HitShape, Collider, CollisionDetection, Layers},
+    components::{Enemy, SpriteSize, Laser, Movable, FromEnemy, PreviousPosition, Velocity},
+    events::{EnemyDestroyed, LaserFired, Shooter, WaveCleared},
+    game_clock_step, EnemyCount, GameClock, GameConfig, GameRng, GameTextures, Movement, TimeScale, Wave, WaveSpawned, WinSize, ENEMY_HULL, SPRITE_SCALE, TIME_STEP,
 };
-use bevy::{prelude::*, time::FixedTimestep, ecs::schedule::ShouldRun};
-use rand::{thread_rng, Rng};
+use bevy::{prelude::*, ecs::schedule::ShouldRun};
+use rand::Rng;
 
-use self::formation::FormationMaker;
+use self::formation::{Formation, FormationMaker};
 
 pub mod formation;
 
+/// Component - which kind of enemy this is
+#[derive(Clone, Copy, Component, Debug, PartialEq, Eq)]
+pub enum EnemyKind {
+    Invader,
+}
+
+impl EnemyKind {
+    pub const ALL: [EnemyKind; 1] = [EnemyKind::Invader];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EnemyKind::Invader => "invader",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
 pub struct EnemyPlugin;
 
+/// Label - counts destroyed enemies, sending `WaveCleared` when the whole
+/// wave is down
+#[derive(Clone, Debug, PartialEq, Eq, Hash, SystemLabel)]
+pub struct EnemyCounting;
+
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
         app
             .insert_resource(FormationMaker::default())
+            .init_resource::<GameConfig>()
+            .init_resource::<GameRng>()
+            .init_resource::<TimeScale>()
+            .init_resource::<GameClock>()
+            .init_resource::<Wave>()
+            .init_resource::<WaveSpawned>()
+            .add_event::<LaserFired>()
+            .add_event::<EnemyDestroyed>()
+            .add_event::<WaveCleared>()
         .add_system_set(
             SystemSet::new()
             // .with_run_criteria(step)
-            .with_run_criteria(FixedTimestep::step(1.))
+            .with_run_criteria(game_clock_step(1.))
             .with_system(enemy_spawn_system),
         )
         .add_system_set(
         SystemSet::new()
             .with_run_criteria(enemy_fire_criteria)
-            .with_system(enemy_fire_system)
+            .with_system(enemy_fire_system.after(CollisionDetection))
         )
-        .add_system(enemy_movement_system);
+        .add_system(enemy_movement_system.label(Movement))
+        .add_system_to_stage(CoreStage::PostUpdate, enemy_destroyed_system.label(EnemyCounting));
     }
 }
 
-fn enemy_fire_criteria() -> ShouldRun {
-    if thread_rng().gen_bool(1. / 60.) {
+fn enemy_destroyed_system(
+    config: Res<GameConfig>,
+    mut enemy_count: ResMut<EnemyCount>,
+    mut wave: ResMut<Wave>,
+    mut wave_spawned: ResMut<WaveSpawned>,
+    mut enemy_destroyed_events: EventReader<EnemyDestroyed>,
+    mut wave_cleared_events: EventWriter<WaveCleared>,
+) {
+    let destroyed = enemy_destroyed_events.iter().count() as u32;
+    if destroyed > 0 {
+        enemy_count.0 = enemy_count.0.saturating_sub(destroyed);
+        // none on screen is only a gap between spawns until the wave is all out
+        if enemy_count.0 == 0 && wave_spawned.0 >= config.wave_size {
+            wave_cleared_events.send(WaveCleared);
+            wave.0 += 1;
+            wave_spawned.0 = 0;
+        }
+    }
+}
+
+fn enemy_fire_criteria(config: Res<GameConfig>, time_scale: Res<TimeScale>, mut rng: ResMut<GameRng>) -> ShouldRun {
+    let chance = config.enemy_fire_rate * TIME_STEP as f64 * time_scale.0 as f64;
+    if rng.rng.gen_bool(chance.clamp(0., 1.)) {
         ShouldRun::Yes
     } else {
         ShouldRun::No
@@ -41,57 +101,138 @@ fn enemy_fire_criteria() -> ShouldRun {
 fn enemy_spawn_system(
     mut commands: Commands,
     mut enemy_count: ResMut<EnemyCount>,
+    mut wave_spawned: ResMut<WaveSpawned>,
     mut formation_maker: ResMut<FormationMaker>,
-    game_textures: Res<GameTextures>,
+    mut rng: ResMut<GameRng>,
+    config: Res<GameConfig>,
+    game_textures: Option<Res<GameTextures>>,
     win_size: Res<WinSize>,
 ) {
-    if enemy_count.0 < ENEMY_MAX {
+    let game_textures = match game_textures {
+        Some(game_textures) => game_textures,
+        None => return,
+    };
+    if enemy_count.0 < config.enemy_max && wave_spawned.0 < config.wave_size {
         // get formation and start x/y
-        let formation = formation_maker.make(&win_size);
-        let (x, y) = formation.start;
-
-        commands
-            .spawn_bundle(SpriteBundle {
-                transform: Transform {
-                    translation: Vec3::new(x, y, 10.),
-                    scale: Vec3::new(SPRITE_SCALE, SPRITE_SCALE, 1.),
-                    ..Default::default()
-                },
-                texture: game_textures.enemy.clone(),
-                ..Default::default()
-            })
-            .insert(Enemy)
-            .insert(formation)
-            .insert(SpriteSize::from(ENEMY_SIZE));
+        let formation = formation_maker.make(&win_size, &mut rng.rng);
+        spawn_enemy(&mut commands, &game_textures, EnemyKind::Invader, formation);
 
         enemy_count.0 += 1;
+        wave_spawned.0 += 1;
     }
 }
 
+/// Spawn an enemy at the start of its formation. Callers keep `EnemyCount` up to date.
+pub fn spawn_enemy(
+    commands: &mut Commands,
+    game_textures: &GameTextures,
+    kind: EnemyKind,
+    formation: Formation,
+) -> Entity {
+    let (x, y) = formation.start;
+
+    commands
+        .spawn_bundle(game_textures.bundle(
+            &game_textures.enemy,
+            Transform {
+                translation: Vec3::new(x, y, 10.),
+                scale: Vec3::new(SPRITE_SCALE, SPRITE_SCALE, 1.),
+                ..Default::default()
+            },
+        ))
+        .insert(Enemy)
+        .insert(kind)
+        .insert(formation)
+        .insert(SpriteSize(game_textures.enemy.size()))
+        .insert(Collider::new(Layers::ENEMY, Layers::PLAYER_LASER))
+        .insert(HitShape::polygon(&ENEMY_HULL))
+        .id()
+}
+
 fn enemy_fire_system(
     mut commands: Commands,
-    game_textures: Res<GameTextures>,
+    game_textures: Option<Res<GameTextures>>,
+    mut laser_fired_events: EventWriter<LaserFired>,
     enemy_query: Query<&Transform, With<Enemy>>,
 ){
+    let game_textures = match game_textures {
+        Some(game_textures) => game_textures,
+        None => return,
+    };
+    let laser = game_textures.enemy_laser;
     for enemy_tf in enemy_query.iter() {
 
         let (x, y) = (enemy_tf.translation.x, enemy_tf.translation.y);
+        let translation = Vec3::new(x, y-15., 5.);
 
         commands
-            .spawn_bundle(SpriteBundle{
-                texture: game_textures.enemy_laser.clone(),
-                transform: Transform{
-                    translation: Vec3::new(x, y-15., 0.),
+            .spawn_bundle(game_textures.bundle(
+                &laser,
+                Transform{
+                    translation,
                     scale: Vec3::new(SPRITE_SCALE, SPRITE_SCALE, 1.),
                     ..Default::default()
                 },
-                ..Default::default()
-            })
+            ))
             .insert(Laser)
-            .insert(SpriteSize::from(ENEMY_LASER_SIZE))
+            .insert(SpriteSize(laser.size()))
+            .insert(Collider::new(Layers::ENEMY_LASER, Layers::PLAYER))
+            .insert(HitShape::vertical_capsule(laser.size().into()))
             .insert(FromEnemy)
             .insert(Movable{ auto_despawn: true})
+            .insert(PreviousPosition(translation))
             .insert(Velocity{ x: 0. ,y: -1.});
+
+        laser_fired_events.send(LaserFired {
+            shooter: Shooter::Enemy,
+            position: translation,
+        });
     }
 
-}
\ No newline at end of file
+}
+
+fn enemy_movement_system(
+    time_scale: Res<TimeScale>,
+    mut query: Query<(&mut Transform, &mut Formation), With<Enemy>>
+){
+    let time_step = TIME_STEP * time_scale.0;
+
+    for (mut transform, mut formation) in query.iter_mut() {
+        
+        let (x_org, y_org) = (transform.translation.x, transform.translation.y);
+
+        let max_distance = time_step * formation.speed;
+
+        // 1 for counter clockwise, -1 clockwise
+        let dir: f32 = if formation.start.0 < 0. {1.} else {-1.};
+        let (x_pivot, y_pivot) = formation.pivot;
+        let (x_radius, y_radius) = formation.radius;
+
+        // compute next angle (based on time for now)
+        let angle = formation.angle + dir * formation.speed * time_step / (x_radius.min(y_radius) * PI as f32 / 2.);
+
+        // compute target x/y
+        let x_dst = x_radius * angle.cos() + x_pivot;
+        let y_dst = y_radius * angle.sin() + y_pivot;
+
+        // compute distance
+        let dx = x_org - x_dst;
+        let dy = y_org - y_dst;
+        let distance = (dx*dx + dy*dy).sqrt();
+        let distance_ratio = if distance != 0. { max_distance / distance } else {0.};
+
+        // compute final x/y
+        let x = x_org - dx * distance_ratio;
+        let x = if dx > 0. { x.max(x_dst)} else { x.min(x_dst)};
+        let y = y_org - dy * distance_ratio;
+        let y = if dy > 0. { y.max(y_dst)} else { y.min(y_dst)};
+
+        // start rotating the formation angle only when sprite is on or close to ellipse
+        if distance < max_distance * formation.speed / 20. {
+            formation.angle = angle;
+        }
+
+        let translation = &mut transform.translation;
+        (translation.x, translation.y) = (x, y);
+    }
+}
@@ -0,0 +1,75 @@
+use bevy::prelude::{Entity, Vec3};
+
+/// Who fired a laser
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shooter {
+    Player,
+    Enemy,
+}
+
+/// Event - a laser was spawned
+#[derive(Clone, Copy, Debug)]
+pub struct LaserFired {
+    pub shooter: Shooter,
+    pub position: Vec3,
+}
+
+/// Event - an enemy was hit and despawned
+#[derive(Clone, Copy, Debug)]
+pub struct EnemyDestroyed {
+    pub enemy: Entity,
+    pub position: Vec3,
+    /// number of the player who shot it, if any
+    pub by_player: Option<usize>,
+}
+
+/// Event - a player was hit and despawned
+#[derive(Clone, Copy, Debug)]
+pub struct PlayerHit {
+    pub player: Entity,
+    pub number: usize,
+    pub position: Vec3,
+}
+
+/// Event - the last enemy on screen was destroyed
+#[derive(Clone, Copy, Debug)]
+pub struct WaveCleared;
+
+/// Kinds of power-up, named after the `assets/PNG/Power-ups` icons
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerUpKind {
+    Shield,
+    Bolt,
+    Star,
+}
+
+impl PowerUpKind {
+    pub const ALL: [PowerUpKind; 3] = [PowerUpKind::Shield, PowerUpKind::Bolt, PowerUpKind::Star];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PowerUpKind::Shield => "shield",
+            PowerUpKind::Bolt => "bolt",
+            PowerUpKind::Star => "star",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
+/// Event - the player picked up a power-up
+#[derive(Clone, Copy, Debug)]
+pub struct PowerUpCollected {
+    pub player: Entity,
+    pub kind: PowerUpKind,
+    pub position: Vec3,
+}
+
+/// Event - a player's shield went down, worn out or hit
+#[derive(Clone, Copy, Debug)]
+pub struct ShieldDown {
+    pub player: Entity,
+    pub position: Vec3,
+}
@@ -0,0 +1,111 @@
+use bevy::{
+    input::{
+        gamepad::{GamepadEvent, GamepadEventType, GamepadSettings, Gamepads},
+        InputSystem,
+    },
+    prelude::*,
+};
+
+use crate::{controls::ControlsSystem, MAX_PLAYERS};
+
+/// Resource - the gamepad of each player, if one is plugged in for them
+#[derive(Default)]
+pub struct PlayerGamepads(pub [Option<Gamepad>; MAX_PLAYERS]);
+
+impl PlayerGamepads {
+    /// Left stick of player `number`'s pad, each axis in -1..=1, +y is up.
+    pub fn stick(&self, number: usize, axes: &Axis<GamepadAxis>) -> Vec2 {
+        let gamepad = match self.0[number] {
+            Some(gamepad) => gamepad,
+            None => return Vec2::ZERO,
+        };
+        let stick = |axis_type| {
+            axes.get(GamepadAxis::new(gamepad, axis_type))
+                .unwrap_or(0.)
+                .clamp(-1., 1.)
+        };
+
+        Vec2::new(
+            stick(GamepadAxisType::LeftStickX),
+            stick(GamepadAxisType::LeftStickY),
+        )
+    }
+
+    pub fn pressed(
+        &self,
+        number: usize,
+        buttons: &Input<GamepadButton>,
+        button_type: GamepadButtonType,
+    ) -> bool {
+        self.0[number]
+            .is_some_and(|gamepad| buttons.pressed(GamepadButton::new(gamepad, button_type)))
+    }
+
+    /// The first button of player `number`'s pad that went down this frame.
+    pub fn just_pressed_button(
+        &self,
+        number: usize,
+        buttons: &Input<GamepadButton>,
+    ) -> Option<GamepadButtonType> {
+        let gamepad = self.0[number]?;
+        buttons
+            .get_just_pressed()
+            .find(|button| button.gamepad == gamepad)
+            .map(|button| button.button_type)
+    }
+}
+
+/// Plugin - hands gamepads to players as pads are plugged in and out
+///
+/// Buttons and the stick reach gameplay through `controls::PlayerActions`.
+pub struct GamepadPlugin;
+
+impl Plugin for GamepadPlugin {
+    fn build(&self, app: &mut App) {
+        // already there with `InputPlugin`, headless apps need them too
+        app.add_event::<GamepadEvent>()
+            .init_resource::<Gamepads>()
+            .init_resource::<GamepadSettings>()
+            .init_resource::<Input<GamepadButton>>()
+            .init_resource::<Axis<GamepadAxis>>()
+            .init_resource::<PlayerGamepads>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                gamepad_hotplug_system
+                    .after(InputSystem)
+                    .before(ControlsSystem),
+            );
+    }
+}
+
+fn gamepad_hotplug_system(
+    gamepads: Res<Gamepads>,
+    mut player_gamepads: ResMut<PlayerGamepads>,
+    mut gamepad_events: EventReader<GamepadEvent>,
+) {
+    for event in gamepad_events.iter() {
+        let slots = &mut player_gamepads.0;
+        match event.event_type {
+            GamepadEventType::Connected if !slots.contains(&Some(event.gamepad)) => {
+                if let Some(number) = slots.iter().position(Option::is_none) {
+                    info!("{:?} steers player {}", event.gamepad, number + 1);
+                    slots[number] = Some(event.gamepad);
+                }
+            }
+            GamepadEventType::Disconnected => {
+                if let Some(number) = slots.iter().position(|slot| *slot == Some(event.gamepad)) {
+                    // hand over to a connected pad nobody is using, if any
+                    let spare = gamepads
+                        .iter()
+                        .copied()
+                        .filter(|gamepad| {
+                            *gamepad != event.gamepad && !slots.contains(&Some(*gamepad))
+                        })
+                        .min_by_key(|gamepad| gamepad.id);
+                    slots[number] = spare;
+                }
+            }
+            _ => {}
+        }
+    }
+}
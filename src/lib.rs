@@ -0,0 +1,561 @@
+#![allow(clippy::type_complexity, clippy::too_many_arguments)]
+
+use std::collections::HashMap;
+
+use animation::AnimationPlugin;
+use atlas::{SheetSprite, SpriteSheet, SpriteSheetPlugin};
+use background::BackgroundPlugin;
+use bevy::{ecs::schedule::ShouldRun, prelude::*};
+use camera::{CameraEffects, CameraEffectsPlugin};
+use collision::CollisionPlugin;
+use components::{ExplosionKind, Movable, PreviousPosition, Velocity};
+use console::ConsolePlugin;
+use controls::ControlsPlugin;
+use debug::DebugPlugin;
+use display::DisplayPlugin;
+use enemy::EnemyPlugin;
+use events::{EnemyDestroyed, LaserFired, PlayerHit, PowerUpCollected, ShieldDown, WaveCleared};
+use gamepad::GamepadPlugin;
+use loading::{Loading, LoadingPlugin};
+use particles::ParticlePlugin;
+use player::{player_ship_sprite, PlayerPlugin};
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+use screens::ScreensPlugin;
+use settings::Settings;
+use sound::SoundPlugin;
+
+pub mod animation;
+pub mod atlas;
+pub mod background;
+pub mod camera;
+pub mod collision;
+pub mod components;
+pub mod console;
+pub mod controls;
+pub mod debug;
+pub mod display;
+pub mod enemy;
+pub mod events;
+pub mod gamepad;
+pub mod loading;
+pub mod manifest;
+pub mod netcode;
+pub mod particles;
+pub mod player;
+pub mod scores;
+pub mod screens;
+pub mod settings;
+pub mod sound;
+
+// Game Constants
+pub const SPRITE_SHEET: &str = "Spritesheet/sheet.xml";
+// explosion sheets, grids and frame rates are in ExplosionKind::sheet
+pub const EXPLOSION_SHEET: &str = "explo_a_sheet.png";
+pub const FIREBALL_SHEET: &str = "exp2_0.png";
+pub const BLAST_SHEET: &str = "exp3_0.jpg";
+pub const UI_FONT: &str = "Bonus/kenvector_future_thin.ttf";
+// one per wave in turn
+pub const BACKGROUND_TILES: [&str; 4] = [
+    "Backgrounds/black.png",
+    "Backgrounds/darkPurple.png",
+    "Backgrounds/blue.png",
+    "Backgrounds/purple.png",
+];
+
+// sprites of SPRITE_SHEET by name, sizes come from the sheet
+// (player ships: see player_ship_sprite)
+pub const PLAYER_LASER_SPRITE: &str = "laserBlue01.png";
+pub const ENEMY_SPRITE: &str = "enemyBlack1.png";
+pub const ENEMY_LASER_SPRITE: &str = "laserRed01.png";
+// engine flame frames, played back and forth
+pub const ENGINE_FIRE_SPRITES: [&str; 3] = ["fire13.png", "fire16.png", "fire17.png"];
+pub const ENGINE_FIRE_FPS: f32 = 12.;
+// engine trail particles per second
+pub const ENGINE_TRAIL_RATE: f32 = 40.;
+// particle sprites
+pub const SPARK_SPRITES: [&str; 3] = ["star1.png", "star2.png", "star3.png"];
+pub const DEBRIS_SPRITES: [&str; 2] = ["meteorGrey_tiny1.png", "meteorGrey_tiny2.png"];
+// enemy hull in sprite pixels (wings and body, the top corners are empty)
+pub const ENEMY_HULL: [(f32, f32); 6] = [
+    (-46., 20.),
+    (-30., 37.),
+    (30., 37.),
+    (46., 20.),
+    (40., -37.),
+    (-40., -37.),
+];
+
+// starting window size, and the size of the playfield it's scaled from
+pub const WINDOW_WIDTH: i32 = 800;
+pub const WINDOW_HEIGHT: i32 = 720;
+pub const SPRITE_SCALE: f32 = 0.5;
+pub const TIME_STEP: f32 = 1. / 60.;
+pub const BASE_SPEED: f32 = 500.;
+
+pub const MAX_PLAYERS: usize = 2;
+pub const PLAYER_LIVES: u32 = 3;
+pub const ENEMY_POINTS: u32 = 100;
+pub const ENEMY_MAX: u32 = 2;
+pub const FORMATION_MEMBERS_MAX: u32 = 2;
+/// enemies spawned in a wave, it is cleared once they are all destroyed
+pub const WAVE_SIZE: u32 = 10;
+pub const PLAYER_RESPAWN_DELAY: f64 = 2.;
+/// seconds a shield power-up lasts, unless it takes a hit first
+pub const SHIELD_DURATION: f64 = 10.;
+pub const ENEMY_FIRE_RATE: f64 = 1.;
+// player velocity change per second, in units of full speed
+pub const PLAYER_ACCELERATION: f32 = 6.;
+pub const PLAYER_DECELERATION: f32 = 10.;
+// END: Game Constants
+/// Resource - the logical playfield in world units, and the window it is
+/// shown in
+///
+/// The playfield stays the same size whatever the window does, `window`
+/// follows `WindowResized` and `display::Letterbox` fits one to the other.
+pub struct WinSize {
+    pub w: f32,
+    pub h: f32,
+    /// logical size of the primary window, in pixels
+    pub window: Vec2,
+}
+
+impl WinSize {
+    /// A `w` by `h` playfield, in a window of the same size.
+    pub fn new(w: f32, h: f32) -> Self {
+        Self {
+            w,
+            h,
+            window: Vec2::new(w, h),
+        }
+    }
+}
+
+/// Resource - the sprite sheet and explosion atlases, until the sheet has
+/// loaded and `GameTextures` can be made
+pub struct TextureHandles {
+    pub sheet: Handle<SpriteSheet>,
+    pub explosions: HashMap<ExplosionKind, Handle<TextureAtlas>>,
+}
+
+/// Resource - the game's sprites, there once the sprite sheet has loaded
+pub struct GameTextures {
+    pub sheet: Handle<TextureAtlas>,
+    pub sheet_image: Handle<Image>,
+    /// ship of each player, in their chosen colour
+    pub players: [SheetSprite; MAX_PLAYERS],
+    pub player_laser: SheetSprite,
+    pub enemy: SheetSprite,
+    pub enemy_laser: SheetSprite,
+    pub engine_fire: [SheetSprite; ENGINE_FIRE_SPRITES.len()],
+    pub sparks: [SheetSprite; SPARK_SPRITES.len()],
+    pub debris: [SheetSprite; DEBRIS_SPRITES.len()],
+    pub explosions: HashMap<ExplosionKind, Handle<TextureAtlas>>,
+}
+
+impl GameTextures {
+    /// Look every sprite up in `sheet`, failing on the first missing one.
+    pub fn new(
+        sheet: &SpriteSheet,
+        settings: &Settings,
+        explosions: HashMap<ExplosionKind, Handle<TextureAtlas>>,
+    ) -> Result<Self, String> {
+        let mut players = [SheetSprite::default(); MAX_PLAYERS];
+        for (number, player) in players.iter_mut().enumerate() {
+            *player = sheet.get(&player_ship_sprite(number, settings.ship_colours[number]))?;
+        }
+        let mut engine_fire = [SheetSprite::default(); ENGINE_FIRE_SPRITES.len()];
+        for (frame, name) in engine_fire.iter_mut().zip(ENGINE_FIRE_SPRITES) {
+            *frame = sheet.get(name)?;
+        }
+        let mut sparks = [SheetSprite::default(); SPARK_SPRITES.len()];
+        for (sprite, name) in sparks.iter_mut().zip(SPARK_SPRITES) {
+            *sprite = sheet.get(name)?;
+        }
+        let mut debris = [SheetSprite::default(); DEBRIS_SPRITES.len()];
+        for (sprite, name) in debris.iter_mut().zip(DEBRIS_SPRITES) {
+            *sprite = sheet.get(name)?;
+        }
+        Ok(Self {
+            sheet: sheet.atlas.clone(),
+            sheet_image: sheet.image.clone(),
+            players,
+            player_laser: sheet.get(PLAYER_LASER_SPRITE)?,
+            enemy: sheet.get(ENEMY_SPRITE)?,
+            enemy_laser: sheet.get(ENEMY_LASER_SPRITE)?,
+            engine_fire,
+            sparks,
+            debris,
+            explosions,
+        })
+    }
+
+    /// Atlas of the `kind` explosion, the default handle if it has none.
+    pub fn explosion(&self, kind: ExplosionKind) -> Handle<TextureAtlas> {
+        self.explosions.get(&kind).cloned().unwrap_or_default()
+    }
+
+    /// Bundle drawing `sprite` of the sheet.
+    pub fn bundle(&self, sprite: &SheetSprite, transform: Transform) -> SpriteSheetBundle {
+        SpriteSheetBundle {
+            sprite: TextureAtlasSprite::new(sprite.index),
+            texture_atlas: self.sheet.clone(),
+            transform,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct EnemyCount(pub u32);
+
+/// Resource - current wave number, starting at 1
+#[derive(Clone)]
+pub struct Wave(pub u32);
+
+/// Resource - enemies spawned so far in the current wave
+#[derive(Clone, Default)]
+pub struct WaveSpawned(pub u32);
+
+impl Default for Wave {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+/// Resource - random numbers of the current run, seeded so it can be
+/// identified and played again
+#[derive(Clone)]
+pub struct GameRng {
+    pub seed: u64,
+    pub rng: StdRng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::new(thread_rng().gen())
+    }
+}
+
+/// Resource - gameplay speed multiplier (1 is normal, 0 freezes movement)
+///
+/// Scales movement, animations, enemy fire and the `GameClock` that times
+/// spawns.
+#[derive(Clone)]
+pub struct TimeScale(pub f32);
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        Self(1.)
+    }
+}
+
+/// Resource - seconds of gameplay, `TIME_STEP` a frame at the `TimeScale`
+///
+/// Stands still while paused, and behind the title and game over screens,
+/// so timed spawns and respawns wait too.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GameClock {
+    pub seconds: f64,
+    /// seconds added by the current frame
+    pub delta: f64,
+}
+
+impl GameClock {
+    pub fn advance(&mut self, time_scale: &TimeScale) {
+        self.delta = TIME_STEP as f64 * time_scale.0.max(0.) as f64;
+        self.seconds += self.delta;
+    }
+
+    /// A multiple of `period` seconds went by in the current frame.
+    pub fn every(&self, period: f64) -> bool {
+        let before = self.seconds - self.delta;
+        self.delta > 0. && (self.seconds / period).floor() > (before / period).floor()
+    }
+}
+
+/// Run criteria - every `period` seconds of `GameClock` time
+pub fn game_clock_step(period: f64) -> impl FnMut(Res<GameClock>) -> ShouldRun {
+    move |clock: Res<GameClock>| {
+        if clock.every(period) {
+            ShouldRun::Yes
+        } else {
+            ShouldRun::No
+        }
+    }
+}
+
+pub fn game_clock_system(time_scale: Res<TimeScale>, mut clock: ResMut<GameClock>) {
+    clock.advance(&time_scale);
+}
+
+/// Resource - whether the game is paused
+///
+/// Pausing parks `TimeScale` at 0 and resuming restores the previous scale.
+#[derive(Clone, Default)]
+pub struct Paused {
+    pub paused: bool,
+    resume_scale: f32,
+}
+
+impl Paused {
+    pub fn toggle(&mut self, time_scale: &mut TimeScale) {
+        if self.paused {
+            time_scale.0 = self.resume_scale;
+        } else {
+            self.resume_scale = time_scale.0;
+            time_scale.0 = 0.;
+        }
+        self.paused = !self.paused;
+    }
+}
+
+/// Resource - which screen is up, gameplay stays paused outside `Playing`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Screen {
+    /// waiting for the assets, or showing why they failed
+    Loading,
+    /// main menu, with the high scores
+    Title,
+    #[default]
+    Playing,
+    /// every player is out of lives
+    GameOver,
+}
+
+/// Resource - gameplay tunables, defaults from the game constants
+pub struct GameConfig {
+    pub enemy_max: u32,
+    /// enemies spawned in each wave
+    pub wave_size: u32,
+    pub player_respawn_delay: f64,
+    /// enemy volleys per second
+    pub enemy_fire_rate: f64,
+    /// let the player move up and down within the bottom third
+    pub player_vertical: bool,
+    /// 1, or 2 for local co-op
+    pub players: usize,
+    /// player lasers hit the other player
+    pub friendly_fire: bool,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            enemy_max: ENEMY_MAX,
+            wave_size: WAVE_SIZE,
+            player_respawn_delay: PLAYER_RESPAWN_DELAY,
+            enemy_fire_rate: ENEMY_FIRE_RATE,
+            player_vertical: false,
+            players: 1,
+            friendly_fire: false,
+        }
+    }
+}
+
+impl GameConfig {
+    pub const KEYS: [&'static str; 7] = [
+        "enemy_max",
+        "wave_size",
+        "player_respawn_delay",
+        "enemy_fire_rate",
+        "player_vertical",
+        "players",
+        "friendly_fire",
+    ];
+
+    /// Set a tunable by name from its text value.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let invalid = || format!("invalid value for {key}: {value}");
+        match key {
+            "enemy_max" => self.enemy_max = value.parse().map_err(|_| invalid())?,
+            "wave_size" => self.wave_size = value.parse().map_err(|_| invalid())?,
+            "player_respawn_delay" => {
+                self.player_respawn_delay = value.parse().map_err(|_| invalid())?
+            }
+            "enemy_fire_rate" => self.enemy_fire_rate = value.parse().map_err(|_| invalid())?,
+            "player_vertical" => self.player_vertical = value.parse().map_err(|_| invalid())?,
+            "players" => match value.parse() {
+                Ok(players) if (1..=MAX_PLAYERS).contains(&players) => self.players = players,
+                _ => return Err(format!("players must be 1 to {MAX_PLAYERS}")),
+            },
+            "friendly_fire" => self.friendly_fire = value.parse().map_err(|_| invalid())?,
+            _ => {
+                return Err(format!(
+                    "unknown config key {key} (one of: {})",
+                    Self::KEYS.join(", ")
+                ))
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Resource - font for overlays and menus
+pub struct UiFont(pub Handle<Font>);
+
+impl FromWorld for UiFont {
+    fn from_world(world: &mut World) -> Self {
+        // headless apps have no asset server
+        let font = world
+            .get_resource::<AssetServer>()
+            .map_or_else(Handle::default, |asset_server| asset_server.load(UI_FONT));
+        Self(font)
+    }
+}
+
+#[derive(Clone)]
+pub struct PlayerState {
+    pub alive: bool,     // alive
+    pub last_shot: f64,  // -1 if not shot
+    pub god: bool,       // enemy lasers pass through
+    pub lives: u32,      // spawns left, counting the current ship
+    pub score: u32,
+}
+impl Default for PlayerState {
+    fn default() -> Self {
+        Self {
+            alive: false,
+            last_shot: -1.,
+            god: false,
+            lives: PLAYER_LIVES,
+            score: 0,
+        }
+    }
+}
+
+impl PlayerState {
+    pub fn shot(&mut self, time: f64){
+        self.alive = false;
+        self.last_shot = time;
+        self.lives = self.lives.saturating_sub(1);
+    }
+
+    pub fn spawned(&mut self){
+        self.alive = true;
+        self.last_shot = -1.;
+    }
+}
+
+/// Resource - state of each player, indexed by `Player` number
+#[derive(Clone, Default)]
+pub struct Players(pub [PlayerState; MAX_PLAYERS]);
+
+/// Plugin - the whole game (setup + display + loading + background + sound + animation + particles + camera effects + controls + player + gamepad + enemies + collisions + screens + debug overlay + console)
+///
+/// Expects `DefaultPlugins` (window, assets, rendering) to be added first.
+pub struct GamePlugin;
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(EnemyCount(0_u32))
+            .insert_resource(Screen::Loading)
+            .add_event::<LaserFired>()
+            .add_event::<EnemyDestroyed>()
+            .add_event::<PlayerHit>()
+            .add_event::<WaveCleared>()
+            .add_event::<PowerUpCollected>()
+            .add_event::<ShieldDown>()
+            .add_startup_system(setup_system)
+            .add_plugin(SpriteSheetPlugin)
+            .add_plugin(DisplayPlugin)
+            .add_plugin(LoadingPlugin)
+            .add_plugin(BackgroundPlugin)
+            .add_plugin(SoundPlugin)
+            .add_plugin(AnimationPlugin)
+            .add_plugin(ParticlePlugin)
+            .add_plugin(CameraEffectsPlugin)
+            .add_plugin(ControlsPlugin)
+            .add_plugin(PlayerPlugin)
+            .add_plugin(GamepadPlugin)
+            .add_plugin(EnemyPlugin)
+            .add_plugin(CollisionPlugin)
+            .add_plugin(ScreensPlugin)
+            .add_plugin(DebugPlugin)
+            .add_plugin(ConsolePlugin);
+    }
+}
+
+fn setup_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut loading: ResMut<Loading>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+) {
+    // the playfield stays the same size whatever the window does
+    let win_size = WinSize::new(WINDOW_WIDTH as f32, WINDOW_HEIGHT as f32);
+
+    // create the explosion texture atlases
+    let mut explosions = HashMap::new();
+    for kind in ExplosionKind::ALL {
+        let sheet = kind.sheet();
+        let texture_handle = asset_server.load(sheet.path);
+        loading.add(&texture_handle);
+        let texture_atlas = TextureAtlas::from_grid(
+            texture_handle,
+            Vec2::splat(sheet.tile_size),
+            sheet.columns,
+            sheet.frames.div_ceil(sheet.columns),
+        );
+        explosions.insert(kind, texture_atlases.add(texture_atlas));
+    }
+
+    // GameTextures follows once the sheet has loaded
+    let sheet = asset_server.load(SPRITE_SHEET);
+    loading.add(&sheet);
+    commands.insert_resource(TextureHandles { sheet, explosions });
+
+    // camera, fitted to the window by the `DisplayPlugin`
+    commands
+        .spawn_bundle(Camera2dBundle::default())
+        .insert(CameraEffects::default());
+
+    commands.insert_resource(win_size);
+}
+
+/// Label - `movable_system` and the enemies' move, for systems that adjust
+/// or read positions after the move
+#[derive(Clone, Debug, PartialEq, Eq, Hash, SystemLabel)]
+pub struct Movement;
+
+pub fn movable_system(
+    mut commands: Commands,
+    win_size: Res<WinSize>,
+    time_scale: Res<TimeScale>,
+    mut query: Query<(
+        Entity,
+        &Velocity,
+        &mut Transform,
+        &Movable,
+        Option<&mut PreviousPosition>,
+    )>,
+) {
+    for (entity, velocity, mut transform, movable, previous) in query.iter_mut() {
+        if let Some(mut previous) = previous {
+            previous.0 = transform.translation;
+        }
+
+        let translation = &mut transform.translation;
+        translation.x += velocity.x * TIME_STEP * BASE_SPEED * time_scale.0;
+        translation.y += velocity.y * TIME_STEP * BASE_SPEED * time_scale.0;
+        if movable.auto_despawn {
+            const MARGIN: f32 = 200.;
+            if translation.y > win_size.h / 2. + MARGIN
+                || translation.y < -win_size.h / 2. - MARGIN
+                || translation.x > win_size.w / 2. + MARGIN
+                || translation.x < -win_size.w / 2. - MARGIN
+            {
+                // println!("->> despawn {entity:?}");
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
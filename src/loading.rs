@@ -0,0 +1,231 @@
+use bevy::{
+    asset::{Asset, LoadState},
+    prelude::*,
+};
+
+use crate::{
+    atlas::SpriteSheet, settings::Settings, GameTextures, Screen, TextureHandles, UiFont,
+    SPRITE_SHEET,
+};
+
+const BAR_WIDTH: f32 = 300.;
+const BAR_HEIGHT: f32 = 16.;
+const BAR_COLOR: Color = Color::rgb(0.3, 0.8, 1.);
+
+/// Resource - assets the game waits for before showing the title screen
+#[derive(Default)]
+pub struct Loading {
+    handles: Vec<HandleUntyped>,
+    loaded: usize,
+    /// why the game can't start, shown instead of the progress bar
+    pub error: Option<String>,
+}
+
+impl Loading {
+    pub fn add<T: Asset>(&mut self, handle: &Handle<T>) {
+        self.handles.push(handle.clone_untyped());
+    }
+
+    /// Share of the handles loaded so far, 0 to 1.
+    pub fn progress(&self) -> f32 {
+        if self.handles.is_empty() {
+            1.
+        } else {
+            self.loaded as f32 / self.handles.len() as f32
+        }
+    }
+}
+
+/// Component - loading screen background node
+#[derive(Component)]
+struct LoadingRoot;
+
+/// Component - loading screen text, the error when loading failed
+#[derive(Component)]
+struct LoadingText;
+
+/// Component - progress bar, the `LoadingBarFill` is inside it
+#[derive(Component)]
+struct LoadingBar;
+
+/// Component - filled part of the progress bar
+#[derive(Component)]
+struct LoadingBarFill;
+
+/// Plugin - `Screen::Loading`: wait for the `Loading` assets with a progress
+/// bar, then make `GameTextures` and go to the title screen
+///
+/// Needs the asset server. Other plugins `add` their handles to `Loading`.
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Loading>()
+            .init_resource::<Screen>()
+            .init_resource::<Settings>()
+            .init_resource::<UiFont>();
+        let font = app.world.resource::<UiFont>().0.clone();
+        app.world.resource_mut::<Loading>().add(&font);
+
+        app.add_system(game_textures_system.before(loading_system))
+            .add_system(loading_system)
+            .add_system(loading_ui_system.after(loading_system));
+    }
+}
+
+/// Make `GameTextures` from the loaded sprite sheet, then wait for its image.
+fn game_textures_system(
+    mut commands: Commands,
+    mut loading: ResMut<Loading>,
+    handles: Option<Res<TextureHandles>>,
+    sheets: Res<Assets<SpriteSheet>>,
+    settings: Res<Settings>,
+    game_textures: Option<Res<GameTextures>>,
+) {
+    let handles = match handles {
+        Some(handles) if game_textures.is_none() && loading.error.is_none() => handles,
+        _ => return,
+    };
+    let sheet = match sheets.get(&handles.sheet) {
+        Some(sheet) => sheet,
+        None => return,
+    };
+
+    match GameTextures::new(sheet, &settings, handles.explosions.clone()) {
+        Ok(game_textures) => {
+            loading.add(&game_textures.sheet_image);
+            commands.insert_resource(game_textures);
+        }
+        Err(err) => {
+            let error = format!("{SPRITE_SHEET}: {err}");
+            error!("{error}");
+            loading.error = Some(error);
+        }
+    }
+}
+
+fn loading_system(
+    asset_server: Res<AssetServer>,
+    mut loading: ResMut<Loading>,
+    mut screen: ResMut<Screen>,
+    game_textures: Option<Res<GameTextures>>,
+) {
+    if *screen != Screen::Loading || loading.error.is_some() {
+        return;
+    }
+
+    let states: Vec<_> = loading
+        .handles
+        .iter()
+        .map(|handle| (handle, asset_server.get_load_state(handle)))
+        .collect();
+    if let Some((handle, _)) = states.iter().find(|(_, state)| *state == LoadState::Failed) {
+        let path = asset_server.get_handle_path(*handle).map_or_else(
+            || "an asset".to_string(),
+            |path| path.path().display().to_string(),
+        );
+        let error = format!("can't load {path}");
+        error!("{error}");
+        loading.error = Some(error);
+        return;
+    }
+    let loaded = states
+        .iter()
+        .filter(|(_, state)| *state == LoadState::Loaded)
+        .count();
+    loading.loaded = loaded;
+
+    if loaded == loading.handles.len() && game_textures.is_some() {
+        *screen = Screen::Title;
+    }
+}
+
+fn loading_ui_system(
+    mut commands: Commands,
+    screen: Res<Screen>,
+    loading: Res<Loading>,
+    font: Res<UiFont>,
+    root_query: Query<Entity, With<LoadingRoot>>,
+    mut text_query: Query<&mut Text, With<LoadingText>>,
+    mut bar_query: Query<
+        (&mut Style, Option<&LoadingBarFill>),
+        Or<(With<LoadingBar>, With<LoadingBarFill>)>,
+    >,
+) {
+    let open = *screen == Screen::Loading;
+    match (open, root_query.get_single()) {
+        (true, Err(_)) => {
+            commands
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                        position_type: PositionType::Absolute,
+                        flex_direction: FlexDirection::ColumnReverse,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    color: Color::BLACK.into(),
+                    ..Default::default()
+                })
+                .insert(LoadingRoot)
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: font.0.clone(),
+                                font_size: 20.,
+                                color: Color::WHITE,
+                            },
+                        ))
+                        .insert(LoadingText);
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Px(BAR_WIDTH), Val::Px(BAR_HEIGHT)),
+                                margin: UiRect::all(Val::Px(12.)),
+                                ..Default::default()
+                            },
+                            color: Color::rgb(0.15, 0.15, 0.15).into(),
+                            ..Default::default()
+                        })
+                        .insert(LoadingBar)
+                        .with_children(|parent| {
+                            parent
+                                .spawn_bundle(NodeBundle {
+                                    style: Style {
+                                        size: Size::new(Val::Percent(0.), Val::Percent(100.)),
+                                        ..Default::default()
+                                    },
+                                    color: BAR_COLOR.into(),
+                                    ..Default::default()
+                                })
+                                .insert(LoadingBarFill);
+                        });
+                });
+        }
+        (false, Ok(root)) => commands.entity(root).despawn_recursive(),
+        _ => {}
+    }
+
+    if !open {
+        return;
+    }
+
+    let message = match &loading.error {
+        Some(error) => format!("{error}\n\nCheck the assets folder and restart the game."),
+        None => "LOADING".to_string(),
+    };
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = message.clone();
+    }
+    for (mut style, fill) in bar_query.iter_mut() {
+        if fill.is_some() {
+            style.size.width = Val::Percent(loading.progress() * 100.);
+        }
+        if loading.error.is_some() {
+            style.display = Display::None;
+        }
+    }
+}
@@ -1,329 +1,76 @@
-use std::{collections::HashSet, default, f64::consts::PI};
-
-use bevy::{ecs::entity, math::Vec3Swizzles, prelude::*, sprite::collide_aabb::collide};
-use components::{
-    Enemy, Explosion, ExplosionTimer, ExplosionToSpawn, FromEnemy, FromPlayer, Laser, Movable,
-    Player, SpriteSize, Velocity,
+use bevy::{
+    prelude::*,
+    window::{MonitorSelection, WindowPosition},
+};
+use space_invaders::{
+    netcode::{self, RollbackSession, SessionConfig, UdpTransport},
+    GameConfig, GamePlugin, MAX_PLAYERS, WINDOW_HEIGHT, WINDOW_WIDTH,
 };
-use enemy::EnemyPlugin;
-use player::PlayerPlugin;
-use enemy::formation::Formation;
-
-mod components;
-mod enemy;
-mod player;
-
-// Game Constants
-const PLAYER_SPRITE: &str = "rusticon.png";
-const PLAYER_LASER_SPRITE: &str = "laser_a_01.png";
-const ENEMY_SPRITE: &str = "enemy_a_01.png";
-const ENEMY_LASER_SPRITE: &str = "laser_b_01.png";
-const EXPLOSION_SHEET: &str = "explo_a_sheet.png";
-
-const PLAYER_SIZE: (f32, f32) = (144., 75.);
-const PLAYER_LASER_SIZE: (f32, f32) = (9., 54.);
-const ENEMY_SIZE: (f32, f32) = (144., 75.);
-const ENEMY_LASER_SIZE: (f32, f32) = (17., 55.);
-const EXPLOSION_LEN: usize = 16;
-
-const WINDOW_WIDTH: i32 = 800;
-const WINDOW_HEIGHT: i32 = 720;
-const SPRITE_SCALE: f32 = 0.5;
-const TIME_STEP: f32 = 1. / 60.;
-const BASE_SPEED: f32 = 500.;
-
-const ENEMY_MAX: u32 = 2;
-const FORMATION_MEMBERS_MAX: u32 = 2;
-const PLAYER_RESPAWN_DELAY: f64 = 2.;
-// END: Game Constants
-pub struct WinSize {
-    pub w: f32,
-    pub h: f32,
-}
-
-pub struct GameTextures {
-    player: Handle<Image>,
-    player_laser: Handle<Image>,
-    enemy: Handle<Image>,
-    enemy_laser: Handle<Image>,
-    explosion: Handle<TextureAtlas>,
-}
 
-struct EnemyCount(u32);
+/// Online game from `--host <port>` or `--join <address>`, player one hosts.
+fn online_session(args: &[String]) -> Result<Option<RollbackSession<UdpTransport, World>>, String> {
+    let arg_after = |flag: &str| {
+        args.iter()
+            .position(|arg| arg == flag)
+            .map(|i| args.get(i + 1).ok_or(format!("{flag} needs a value")))
+            .transpose()
+    };
 
-struct PlayerState {
-    alive: bool,   // alive
-    last_shot: f64,  // -1 if not shot
-}
-impl Default for PlayerState {
-    fn default() -> Self {
-        Self { 
-            alive: false,
-            last_shot: -1.,
+    let (transport, local_player) = match (arg_after("--host")?, arg_after("--join")?) {
+        (Some(_), Some(_)) => return Err("either --host or --join, not both".to_string()),
+        (Some(port), None) => {
+            let port: u16 = port.parse().map_err(|_| format!("bad port {port}"))?;
+            let transport = UdpTransport::listen(("0.0.0.0", port))
+                .map_err(|err| format!("can't listen on port {port}: {err}"))?;
+            (transport, 0)
         }
-    }
-}
-
-impl PlayerState {
-    pub fn shot(&mut self, time: f64){
-        self.alive = false;
-        self.last_shot = time;
-    }
+        (None, Some(address)) => {
+            let transport = UdpTransport::bind("0.0.0.0:0", address.as_str())
+                .map_err(|err| format!("can't join {address}: {err}"))?;
+            (transport, 1)
+        }
+        (None, None) => return Ok(None),
+    };
 
-    pub fn spawned(&mut self){
-        self.alive = true;
-        self.last_shot = -1.;
-    }
+    let config = SessionConfig {
+        local_player,
+        ..Default::default()
+    };
+    Ok(Some(RollbackSession::new(config, transport)))
 }
 
 fn main() {
-    App::new()
-        .insert_resource(ClearColor(Color::rgb(0.04, 0.04, 0.04)))
+    let args: Vec<String> = std::env::args().collect();
+    let session = online_session(&args).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        std::process::exit(2);
+    });
+
+    // `--coop` starts a local two-player game, online games have two players too
+    let players = if session.is_some() || args.iter().any(|arg| arg == "--coop") {
+        MAX_PLAYERS
+    } else {
+        1
+    };
+
+    let mut app = App::new();
+    app.insert_resource(ClearColor(Color::rgb(0.04, 0.04, 0.04)))
         .insert_resource(WindowDescriptor {
             title: "Space Invaders!".to_string(),
             width: WINDOW_WIDTH as f32,
             height: WINDOW_HEIGHT as f32,
+            position: WindowPosition::Centered(MonitorSelection::Current),
+            ..Default::default()
+        })
+        .insert_resource(GameConfig {
+            players,
             ..Default::default()
         })
-        .insert_resource(EnemyCount(0_u32))
         .add_plugins(DefaultPlugins)
-        .add_startup_system(setup_system)
-        .add_plugin(PlayerPlugin)
-        .add_plugin(EnemyPlugin)
-        .add_system(player_laser_hit_enemy_system)
-        .add_system(explosion_to_spawn_system)
-        .add_system(explosion_animation_system)
-        .add_system(enemy_laser_hit_player_system)
-        .run();
-}
-
-fn setup_system(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
-    mut windows: ResMut<Windows>,
-) {
-    // capture window size
-    let window = windows.get_primary_mut().unwrap();
-    let (win_w, win_h) = (window.width(), window.height());
-    let win_size = WinSize { w: win_w, h: win_h };
-
-    // add WinSize resource
-    commands.insert_resource(win_size);
-
-    // create explosion texture atlas
-    let texture_handle = asset_server.load(EXPLOSION_SHEET);
-    let texture_atlas = TextureAtlas::from_grid(texture_handle, Vec2::new(64., 64.), 4, 4);
-    let explosion = texture_atlases.add(texture_atlas);
-
-    // add GameTextures resource
-    let game_textures = GameTextures {
-        player: asset_server.load(PLAYER_SPRITE),
-        player_laser: asset_server.load(PLAYER_LASER_SPRITE),
-        enemy: asset_server.load(ENEMY_SPRITE),
-        enemy_laser: asset_server.load(ENEMY_LASER_SPRITE),
-        explosion: explosion,
-    };
-    commands.insert_resource(game_textures);
-
-    // position of the window : OPTIONAL
-    window.set_position(IVec2::new(
-        (1920 - WINDOW_WIDTH) / 2,
-        (1080 - WINDOW_HEIGHT) / 2,
-    ));
-
-    // camera
-    commands.spawn_bundle(Camera2dBundle::default());
-}
-
-fn movable_system(
-    mut commands: Commands,
-    win_size: Res<WinSize>,
-    mut query: Query<(Entity, &Velocity, &mut Transform, &Movable)>,
-) {
-    for (entity, velocity, mut transform, movable) in query.iter_mut() {
-        let translation = &mut transform.translation;
-        translation.x += velocity.x * TIME_STEP * BASE_SPEED;
-        translation.y += velocity.y * TIME_STEP * BASE_SPEED;
-        if movable.auto_despawn {
-            const MARGIN: f32 = 200.;
-            if translation.y > win_size.h / 2. + MARGIN
-                || translation.y < -win_size.h / 2. - MARGIN
-                || translation.x > win_size.w / 2. + MARGIN
-                || translation.x < -win_size.w / 2. - MARGIN
-            {
-                // println!("->> despawn {entity:?}");
-                commands.entity(entity).despawn();
-            }
-        }
-    }
-}
-
-fn enemy_laser_hit_player_system(
-    mut commands: Commands,
-    mut player_state: ResMut<PlayerState>,
-    time: Res<Time>,
-    laser_query: Query<(Entity, &Transform, &SpriteSize), (With<Laser>, With<FromEnemy>)>,
-    player_query: Query<(Entity, &Transform, &SpriteSize), With<Player>>,
-) {
-    if let Ok((player_entity, player_tf, player_size)) = player_query.get_single() {
-        let player_scale = Vec2::from(player_tf.scale.xy());
-
-        for (laser_entity, laser_tf, laser_size) in laser_query.iter() {
-            let laser_scale = Vec2::from(laser_tf.scale.xy());
+        .add_plugin(GamePlugin);
 
-            let collision = collide(
-                laser_tf.translation,
-                laser_size.0 * laser_scale,
-                player_tf.translation,
-                player_size.0 * player_scale,
-            );
-
-            if let Some(_) = collision {
-                commands.entity(player_entity).despawn();
-                player_state.shot(time.seconds_since_startup());
-
-                commands.entity(laser_entity).despawn();
-
-                commands
-                    .spawn()
-                    .insert(ExplosionToSpawn(player_tf.translation.clone()));
-                
-                break;
-            }
-        }
-    }
-}
-
-fn player_laser_hit_enemy_system(
-    mut commands: Commands,
-    mut enemy_count: ResMut<EnemyCount>,
-    laser_query: Query<(Entity, &Transform, &SpriteSize), (With<Laser>, With<FromPlayer>)>,
-    enemy_query: Query<(Entity, &Transform, &SpriteSize), With<Enemy>>,
-) {
-    let mut despawned_entities: HashSet<Entity> = HashSet::new();
-
-    for (laser_entity, laser_tf, laser_size) in laser_query.iter() {
-        if despawned_entities.contains(&laser_entity) {
-            continue;
-        }
-
-        let laser_scale = Vec2::from(laser_tf.scale.xy());
-
-        for (enemy_entity, enemy_tf, enemy_size) in enemy_query.iter() {
-            if despawned_entities.contains(&enemy_entity)
-                || despawned_entities.contains(&laser_entity)
-            {
-                continue;
-            }
-
-            let enemy_scale = Vec2::from(enemy_tf.scale.xy());
-
-            let collision = collide(
-                laser_tf.translation,
-                laser_size.0 * laser_scale,
-                enemy_tf.translation,
-                enemy_size.0 * enemy_scale,
-            );
-
-            if let Some(_) = collision {
-                commands.entity(enemy_entity).despawn();
-                despawned_entities.insert(enemy_entity);
-                enemy_count.0 -= 1;
-
-                commands.entity(laser_entity).despawn();
-                despawned_entities.insert(laser_entity);
-
-                commands
-                    .spawn()
-                    .insert(ExplosionToSpawn(enemy_tf.translation.clone()));
-            }
-        }
+    if let Some(session) = session {
+        netcode::start(&mut app, session);
     }
+    app.run();
 }
-
-fn explosion_to_spawn_system(
-    mut commands: Commands,
-    game_textures: Res<GameTextures>,
-    query: Query<(Entity, &ExplosionToSpawn)>,
-) {
-    for (explosion_spawn_entity, explosion_to_spawn) in query.iter() {
-        commands
-            .spawn_bundle(SpriteSheetBundle {
-                texture_atlas: game_textures.explosion.clone(),
-                transform: Transform {
-                    translation: explosion_to_spawn.0,
-                    ..Default::default()
-                },
-                ..Default::default()
-            })
-            .insert(Explosion)
-            .insert(ExplosionTimer::default());
-
-        commands.entity(explosion_spawn_entity).despawn();
-    }
-}
-
-fn explosion_animation_system(
-    mut commands: Commands,
-    time: Res<Time>,
-    mut query: Query<(Entity, &mut ExplosionTimer, &mut TextureAtlasSprite), With<Explosion>>,
-) {
-    for (entity, mut timer, mut sprite) in query.iter_mut() {
-        timer.0.tick(time.delta());
-
-        if timer.0.finished() {
-            sprite.index += 1;
-            if sprite.index >= EXPLOSION_LEN {
-                commands.entity(entity).despawn();
-            }
-        }
-    }
-}
-
-
-fn enemy_movement_system(
-    time: Res<Time>,
-    mut query: Query<(&mut Transform, &mut Formation), With<Enemy>>
-){
-    let now = time.seconds_since_startup() as f32;
-
-    for (mut transform, mut formation) in query.iter_mut() {
-        
-        let (x_org, y_org) = (transform.translation.x, transform.translation.y);
-
-        let max_distance = TIME_STEP * formation.speed;
-
-        // 1 for counter clockwise, -1 clockwise
-        let dir: f32 = if formation.start.0 < 0. {1.} else {-1.};
-        let (x_pivot, y_pivot) = formation.pivot;
-        let (x_radius, y_radius) = formation.radius;
-
-        // compute next angle (based on time for now)
-        let angle = formation.angle + dir * formation.speed * TIME_STEP / (x_radius.min(y_radius) * PI as f32 / 2.);
-
-        // compute target x/y
-        let x_dst = x_radius * angle.cos() + x_pivot;
-        let y_dst = y_radius * angle.sin() + y_pivot;
-
-        // compute distance
-        let dx = x_org - x_dst;
-        let dy = y_org - y_dst;
-        let distance = (dx*dx + dy*dy).sqrt();
-        let distance_ratio = if distance != 0. { max_distance / distance } else {0.};
-
-        // compute final x/y
-        let x = x_org - dx * distance_ratio;
-        let x = if dx > 0. { x.max(x_dst)} else { x.min(x_dst)};
-        let y = y_org - dy * distance_ratio;
-        let y = if dy > 0. { y.max(y_dst)} else { y.min(y_dst)};
-
-        // start rotating the formation angle only when sprite is on or close to ellipse
-        if distance < max_distance * formation.speed / 20. {
-            formation.angle = angle;
-        }
-
-        let translation = &mut transform.translation;
-        (translation.x, translation.y) = (x, y);
-    }
-}
\ No newline at end of file
@@ -0,0 +1,321 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::Cursor,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    prelude::*,
+    render::texture::{CompressedImageFormats, ImageType},
+};
+
+use crate::{
+    atlas::SheetListing,
+    components::ExplosionKind,
+    player::{player_ship_sprite, ShipColour},
+    sound::{ogg_length, Sfx},
+    BACKGROUND_TILES, DEBRIS_SPRITES, ENEMY_HULL, ENEMY_LASER_SPRITE, ENEMY_SPRITE,
+    ENGINE_FIRE_SPRITES, MAX_PLAYERS, PLAYER_LASER_SPRITE, SPARK_SPRITES, SPRITE_SHEET, UI_FONT,
+};
+
+/// An image cut into a grid of square tiles, like the explosion sheets
+#[derive(Clone, Debug)]
+pub struct GridSheet {
+    pub path: String,
+    pub tile_size: f32,
+    pub columns: usize,
+    pub tiles: usize,
+}
+
+/// Every asset the game loads, to check the `assets` folder before a release
+/// (see the `check-assets` binary)
+#[derive(Clone, Debug, Default)]
+pub struct AssetManifest {
+    /// relative to the assets folder
+    pub files: Vec<String>,
+    /// sprite names looked up in the `SPRITE_SHEET` listing
+    pub sprites: Vec<String>,
+    pub grids: Vec<GridSheet>,
+    /// convex hitboxes in sprite pixels, by sprite name
+    pub hulls: Vec<(String, Vec<(f32, f32)>)>,
+}
+
+/// What `AssetManifest::check` found
+#[derive(Debug, Default)]
+pub struct AssetReport {
+    pub problems: Vec<String>,
+    /// files in the assets folder the game never loads
+    pub unused: Vec<String>,
+}
+
+impl AssetManifest {
+    /// The assets of the game, with every ship a player can pick in the
+    /// settings.
+    pub fn new() -> Self {
+        let mut sprites: Vec<String> = (0..MAX_PLAYERS)
+            .flat_map(|number| ShipColour::ALL.map(|colour| player_ship_sprite(number, colour)))
+            .collect();
+        sprites.extend([PLAYER_LASER_SPRITE, ENEMY_SPRITE, ENEMY_LASER_SPRITE].map(str::to_string));
+        sprites.extend(ENGINE_FIRE_SPRITES.map(str::to_string));
+        sprites.extend(SPARK_SPRITES.map(str::to_string));
+        sprites.extend(DEBRIS_SPRITES.map(str::to_string));
+
+        let grids: Vec<_> = ExplosionKind::ALL
+            .map(|kind| kind.sheet())
+            .map(|sheet| GridSheet {
+                path: sheet.path.to_string(),
+                tile_size: sheet.tile_size,
+                columns: sheet.columns,
+                tiles: sheet.frames,
+            })
+            .to_vec();
+
+        let mut files: Vec<String> = [SPRITE_SHEET, UI_FONT].map(str::to_string).to_vec();
+        files.extend(grids.iter().map(|grid| grid.path.clone()));
+        files.extend(BACKGROUND_TILES.map(str::to_string));
+        files.extend(Sfx::ALL.map(|sfx| sfx.path().to_string()));
+
+        Self {
+            files,
+            sprites,
+            grids,
+            hulls: vec![(ENEMY_SPRITE.to_string(), ENEMY_HULL.to_vec())],
+        }
+    }
+
+    /// Check every file exists and decodes, every sprite is in the sheet and
+    /// the hitboxes fit their sprites.
+    pub fn check(&self, assets: &Path) -> AssetReport {
+        let mut report = AssetReport::default();
+        let mut used = BTreeSet::new();
+        let mut image_sizes = Vec::new();
+        let mut sheet_sprites = Vec::new();
+
+        for file in self.files.iter() {
+            used.insert(file.clone());
+            let bytes = match fs::read(assets.join(file)) {
+                Ok(bytes) => bytes,
+                Err(err) => {
+                    report.problems.push(format!("{file}: {err}"));
+                    continue;
+                }
+            };
+
+            let checked = match extension(file).as_str() {
+                "png" | "jpg" | "jpeg" => decode_image(&bytes, file).map(|size| {
+                    image_sizes.push((file.clone(), size));
+                }),
+                "ttf" | "otf" => Font::try_from_bytes(bytes)
+                    .map(|_| ())
+                    .map_err(|err| format!("bad font: {err}")),
+                "ogg" => decode_audio(bytes),
+                "xml" => check_sheet(assets, file, &bytes, &mut used).map(|sprites| {
+                    sheet_sprites.extend(sprites);
+                }),
+                other => Err(format!("unknown kind of asset .{other}")),
+            };
+            if let Err(err) = checked {
+                report.problems.push(format!("{file}: {err}"));
+            }
+        }
+
+        for name in self.sprites.iter() {
+            if !sheet_sprites.iter().any(|(sprite, _)| sprite == name) {
+                report
+                    .problems
+                    .push(format!("{SPRITE_SHEET}: no sprite {name}"));
+            }
+        }
+
+        for grid in self.grids.iter() {
+            let size = match image_sizes.iter().find(|(path, _)| *path == grid.path) {
+                Some((_, size)) => *size,
+                None => continue,
+            };
+            let rows = grid.tiles.div_ceil(grid.columns);
+            let needed = Vec2::new(grid.columns as f32, rows as f32) * grid.tile_size;
+            if size.x < needed.x || size.y < needed.y {
+                report.problems.push(format!(
+                    "{}: {}x{} is too small for {} tiles of {}px in {} columns",
+                    grid.path, size.x, size.y, grid.tiles, grid.tile_size, grid.columns
+                ));
+            }
+        }
+
+        for (name, hull) in self.hulls.iter() {
+            let half = match sheet_sprites.iter().find(|(sprite, _)| sprite == name) {
+                Some((_, size)) => *size / 2.,
+                None => continue,
+            };
+            for &(x, y) in hull.iter() {
+                if x.abs() > half.x || y.abs() > half.y {
+                    report.problems.push(format!(
+                        "hitbox point ({x}, {y}) of {name} is outside its {}x{} sprite",
+                        half.x * 2.,
+                        half.y * 2.
+                    ));
+                }
+            }
+        }
+
+        report.unused = asset_files(assets)
+            .into_iter()
+            .filter(|file| !used.contains(file))
+            .collect();
+        report
+    }
+}
+
+/// The sheet listing `file` and its image, returns each sprite's size.
+fn check_sheet(
+    assets: &Path,
+    file: &str,
+    bytes: &[u8],
+    used: &mut BTreeSet<String>,
+) -> Result<Vec<(String, Vec2)>, String> {
+    let text = std::str::from_utf8(bytes).map_err(|err| err.to_string())?;
+    let listing = SheetListing::parse(text)?;
+
+    let image_path = match file.rsplit_once('/') {
+        Some((dir, _)) => format!("{dir}/{}", listing.image_path),
+        None => listing.image_path.clone(),
+    };
+    used.insert(image_path.clone());
+    let image_bytes =
+        fs::read(assets.join(&image_path)).map_err(|err| format!("{image_path}: {err}"))?;
+    let size =
+        decode_image(&image_bytes, &image_path).map_err(|err| format!("{image_path}: {err}"))?;
+
+    for (name, rect) in listing.sprites.iter() {
+        if rect.max.x > size.x || rect.max.y > size.y {
+            return Err(format!("sprite {name} is outside {image_path}"));
+        }
+    }
+    Ok(listing
+        .sprites
+        .into_iter()
+        .map(|(name, rect)| (name, rect.size()))
+        .collect())
+}
+
+fn extension(file: &str) -> String {
+    Path::new(file)
+        .extension()
+        .map_or_else(String::new, |ext| ext.to_string_lossy().to_lowercase())
+}
+
+/// Size of the image, as the game would decode it.
+fn decode_image(bytes: &[u8], file: &str) -> Result<Vec2, String> {
+    let image = Image::from_buffer(
+        bytes,
+        ImageType::Extension(&extension(file)),
+        CompressedImageFormats::all(),
+        true,
+    )
+    .map_err(|err| format!("can't decode: {err}"))?;
+    let size = image.texture_descriptor.size;
+    Ok(Vec2::new(size.width as f32, size.height as f32))
+}
+
+/// Decode every sample, as the game would when playing it.
+///
+/// Bevy unwraps the decoder, so the headers are checked here first and a
+/// broken file is an error instead of a panic.
+fn decode_audio(bytes: Vec<u8>) -> Result<(), String> {
+    if ogg_length(&bytes).is_none() {
+        return Err("not an Ogg Vorbis file".to_string());
+    }
+    let decoder = rodio::Decoder::new_vorbis(Cursor::new(bytes))
+        .map_err(|err| format!("can't decode: {err}"))?;
+    // in case a broken packet still panics past the headers
+    let samples = panic::catch_unwind(AssertUnwindSafe(|| decoder.count()));
+
+    match samples {
+        Ok(0) => Err("no audio".to_string()),
+        Ok(_) => Ok(()),
+        Err(_) => Err("can't decode".to_string()),
+    }
+}
+
+/// Every file under `assets`, relative to it, with `/` separators.
+fn asset_files(assets: &Path) -> Vec<String> {
+    let mut files = Vec::new();
+    let mut dirs = vec![PathBuf::from(assets)];
+    while let Some(dir) = dirs.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.is_dir() {
+                dirs.push(path);
+            } else if let Ok(relative) = path.strip_prefix(assets) {
+                let parts: Vec<_> = relative
+                    .components()
+                    .map(|part| part.as_os_str().to_string_lossy())
+                    .collect();
+                files.push(parts.join("/"));
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assets() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("assets")
+    }
+
+    #[test]
+    fn game_assets_are_all_there() {
+        let manifest = AssetManifest::new();
+        // whatever colours this machine's settings.ron picks
+        assert!(manifest
+            .sprites
+            .contains(&"playerShip1_green.png".to_string()));
+        assert!(manifest
+            .sprites
+            .contains(&"playerShip2_orange.png".to_string()));
+
+        let report = manifest.check(&assets());
+
+        assert_eq!(report.problems, Vec::<String>::new());
+        assert!(!report.unused.contains(&"Spritesheet/sheet.png".to_string()));
+        assert!(report.unused.contains(&"laser_b_01.png".to_string()));
+        assert!(!report.unused.contains(&"Bonus/sfx_zap.ogg".to_string()));
+        assert!(!report.unused.contains(&"exp3_0.jpg".to_string()));
+    }
+
+    #[test]
+    fn reports_missing_files_sprites_and_bad_hitboxes() {
+        let manifest = AssetManifest {
+            files: vec![SPRITE_SHEET.to_string(), "PNG/nothing.png".to_string()],
+            sprites: vec!["nothing.png".to_string()],
+            grids: Vec::new(),
+            hulls: vec![(ENEMY_SPRITE.to_string(), vec![(0., 50.)])],
+        };
+
+        let problems = manifest.check(&assets()).problems;
+        assert_eq!(problems.len(), 3, "{problems:?}");
+        assert!(problems[0].starts_with("PNG/nothing.png"));
+        assert!(problems[1].contains("no sprite nothing.png"));
+        assert!(problems[2].contains("(0, 50) of enemyBlack1.png"));
+    }
+
+    #[test]
+    fn broken_files_do_not_decode() {
+        assert!(decode_image(b"\x89PNG not really", "a.png").is_err());
+        assert!(decode_audio(b"OggS not really".to_vec()).is_err());
+        // headers that look right, with nothing behind them
+        let mut fake = b"OggS\0\0".to_vec();
+        fake.extend(b"\x01vorbis\0\0\0\0\x02\x44\xac\0\0");
+        fake.extend([0; 32]);
+        assert!(decode_audio(fake).is_err());
+    }
+}
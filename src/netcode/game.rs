@@ -0,0 +1,395 @@
+use std::{any::TypeId, collections::VecDeque};
+
+use bevy::{
+    ecs::{
+        component::ComponentId,
+        event::Events,
+        schedule::{Stage, StageLabel},
+        system::CommandQueue,
+    },
+    prelude::*,
+};
+
+use crate::{
+    collision::{
+        shape::{AlphaMaskSource, HitShape},
+        Collider, CollisionEvent,
+    },
+    components::{
+        Enemy, FromEnemy, FromPlayer, Laser, Movable, Player, PreviousPosition, Shield, SpriteSize,
+        Velocity,
+    },
+    controls::{menu::SettingsMenu, ActionFrame, InputSource, InputSources, PlayerActions},
+    enemy::{
+        formation::{Formation, FormationMaker},
+        EnemyKind,
+    },
+    events::{EnemyDestroyed, LaserFired, PlayerHit, PowerUpCollected, ShieldDown, WaveCleared},
+    gamepad::PlayerGamepads,
+    player::spawn_engine_fire,
+    settings::Settings,
+    EnemyCount, GameClock, GameRng, GameTextures, Paused, Players, Screen, TimeScale, Wave,
+    WaveSpawned, MAX_PLAYERS,
+};
+
+use super::{RollbackSession, Simulation, Transport};
+
+/// Seed of every online game, so both peers start the same one
+pub const NETPLAY_SEED: u64 = 0x5eed;
+
+/// Resource - an online game is on, gameplay that isn't rolled back stays out of it
+pub struct Online {
+    /// `Player` number controlled on this peer
+    pub local_player: usize,
+    /// ticks played since the game started, rolled back with it
+    pub tick: u32,
+    /// ticks run at least once, kept through rollbacks
+    pub played: u32,
+    /// the running tick is a replay after a rollback, or the frozen tick
+    /// shown while waiting for the peer: sounds, explosions and other
+    /// effects already played, or will once it is for real
+    pub resimulating: bool,
+}
+
+/// Resource - the app's own schedule, run once per tick by the session
+struct GameSchedule(Schedule);
+
+/// Resource - the rollback session playing the game online
+pub struct Netplay<T: Transport> {
+    session: RollbackSession<T, World>,
+    /// both peers are past loading and playing from the same new game
+    started: bool,
+    /// state under the frame shown while waiting for the peer
+    frozen: Option<GameSnapshot>,
+}
+
+impl<T: Transport> Netplay<T> {
+    pub fn session(&self) -> &RollbackSession<T, World> {
+        &self.session
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, StageLabel)]
+struct NetplayStage;
+
+/// Play `app` online through `session`. Call it once every plugin is added:
+/// it takes over the schedule, running it once per tick, as many times a
+/// frame as rollbacks need.
+pub fn start<T: Transport + Send + Sync + 'static>(
+    app: &mut App,
+    session: RollbackSession<T, World>,
+) {
+    let schedule = std::mem::take(&mut app.schedule);
+    app.insert_resource(GameSchedule(schedule))
+        .insert_resource(Online {
+            local_player: session.local_player(),
+            tick: 0,
+            played: 0,
+            resimulating: false,
+        })
+        .insert_resource(Netplay {
+            session,
+            started: false,
+            frozen: None,
+        })
+        .add_stage(
+            NetplayStage,
+            SystemStage::single(netplay_system::<T>.exclusive_system()),
+        );
+}
+
+fn netplay_system<T: Transport + Send + Sync + 'static>(world: &mut World) {
+    world.resource_scope(|world, mut netplay: Mut<Netplay<T>>| {
+        if let Some(state) = netplay.frozen.take() {
+            world.load(&state);
+        }
+
+        if !netplay.started {
+            run_game_schedule(world);
+            // once the assets are in, both peers start from the same new game
+            if *world.resource::<Screen>() != Screen::Loading {
+                world.load(&GameSnapshot::new_game());
+                netplay.started = true;
+            }
+            return;
+        }
+
+        let input = local_input(world);
+        if !netplay.session.advance_frame(world, input) {
+            // waiting for the peer: a frozen tick keeps the window going, and
+            // is undone before the next one
+            netplay.frozen = Some(world.save());
+            world.resource_mut::<TimeScale>().0 = 0.;
+            world.resource_mut::<Online>().resimulating = true;
+            run_tick(world, [ActionFrame::default(); MAX_PLAYERS]);
+        }
+    });
+}
+
+fn run_game_schedule(world: &mut World) {
+    world.resource_scope(|world, mut schedule: Mut<GameSchedule>| schedule.0.run(world));
+}
+
+/// Run the schedule once, each player's input played by `controls_system`.
+fn run_tick(world: &mut World, inputs: [ActionFrame; MAX_PLAYERS]) {
+    let mut sources = world.resource_mut::<InputSources>();
+    for (source, input) in sources.0.iter_mut().zip(inputs) {
+        *source = InputSource::Replay(VecDeque::from([input]));
+    }
+    run_game_schedule(world);
+}
+
+/// Actions held on this peer's devices, through the first player's bindings.
+fn local_input(world: &World) -> ActionFrame {
+    // the menu has the devices to itself
+    if world.resource::<SettingsMenu>().open {
+        return ActionFrame::default();
+    }
+    world.resource::<Settings>().bindings[0].read(
+        0,
+        world.resource::<Input<KeyCode>>(),
+        world.resource::<PlayerGamepads>(),
+        world.resource::<Input<GamepadButton>>(),
+        world.resource::<Axis<GamepadAxis>>(),
+    )
+}
+
+/// Everything a tick of gameplay reads and changes: the gameplay resources,
+/// and the players, enemies and lasers. Effects and sounds are left out, they
+/// only play on a tick's first run (see `Online::resimulating`).
+#[derive(Clone)]
+pub struct GameSnapshot {
+    tick: u32,
+    screen: Screen,
+    players: Players,
+    actions: PlayerActions,
+    enemy_count: EnemyCount,
+    wave: Wave,
+    wave_spawned: WaveSpawned,
+    rng: GameRng,
+    clock: GameClock,
+    formation_maker: FormationMaker,
+    time_scale: TimeScale,
+    paused: Paused,
+    bodies: Vec<Body>,
+}
+
+impl GameSnapshot {
+    fn new_game() -> Self {
+        Self {
+            tick: 0,
+            screen: Screen::Playing,
+            players: Players::default(),
+            actions: PlayerActions::default(),
+            enemy_count: EnemyCount(0),
+            wave: Wave::default(),
+            wave_spawned: WaveSpawned::default(),
+            rng: GameRng::new(NETPLAY_SEED),
+            clock: GameClock::default(),
+            formation_maker: FormationMaker::default(),
+            time_scale: TimeScale::default(),
+            paused: Paused::default(),
+            bodies: Vec::new(),
+        }
+    }
+}
+
+/// A player, enemy or laser entity
+#[derive(Clone)]
+struct Body {
+    kind: BodyKind,
+    transform: Transform,
+    sprite: TextureAtlasSprite,
+    atlas: Handle<TextureAtlas>,
+    size: SpriteSize,
+    collider: Collider,
+    shape: Option<HitShape>,
+    mask_source: Option<AlphaMaskSource>,
+    velocity: Option<Velocity>,
+    movable: Option<Movable>,
+    previous: Option<PreviousPosition>,
+    shield: Option<Shield>,
+}
+
+#[derive(Clone)]
+enum BodyKind {
+    Player(Player),
+    Enemy(EnemyKind, Formation),
+    /// from a player, or an enemy when `None`
+    Laser(Option<FromPlayer>),
+}
+
+impl Body {
+    fn of(world: &World, entity: Entity) -> Option<Self> {
+        let kind = if let Some(player) = world.get::<Player>(entity) {
+            BodyKind::Player(*player)
+        } else if let (Some(kind), Some(formation)) = (
+            world.get::<EnemyKind>(entity),
+            world.get::<Formation>(entity),
+        ) {
+            BodyKind::Enemy(*kind, formation.clone())
+        } else {
+            BodyKind::Laser(world.get::<FromPlayer>(entity).copied())
+        };
+
+        Some(Self {
+            kind,
+            transform: *world.get::<Transform>(entity)?,
+            sprite: world.get::<TextureAtlasSprite>(entity)?.clone(),
+            atlas: world.get::<Handle<TextureAtlas>>(entity)?.clone(),
+            size: world.get::<SpriteSize>(entity)?.clone(),
+            collider: *world.get::<Collider>(entity)?,
+            shape: world.get::<HitShape>(entity).cloned(),
+            mask_source: world.get::<AlphaMaskSource>(entity).cloned(),
+            velocity: world.get::<Velocity>(entity).cloned(),
+            movable: world.get::<Movable>(entity).cloned(),
+            previous: world.get::<PreviousPosition>(entity).cloned(),
+            shield: world.get::<Shield>(entity).cloned(),
+        })
+    }
+
+    fn spawn(&self, commands: &mut Commands, game_textures: Option<&GameTextures>) {
+        let mut entity = commands.spawn_bundle(SpriteSheetBundle {
+            sprite: self.sprite.clone(),
+            texture_atlas: self.atlas.clone(),
+            transform: self.transform,
+            // drawn where it is even if no tick runs before the next render
+            global_transform: self.transform.into(),
+            ..Default::default()
+        });
+        entity.insert(self.size.clone()).insert(self.collider);
+        if let Some(shape) = &self.shape {
+            entity.insert(shape.clone());
+        }
+        if let Some(mask_source) = &self.mask_source {
+            entity.insert(mask_source.clone());
+        }
+        if let Some(velocity) = &self.velocity {
+            entity.insert(velocity.clone());
+        }
+        if let Some(movable) = &self.movable {
+            entity.insert(movable.clone());
+        }
+        if let Some(previous) = &self.previous {
+            entity.insert(previous.clone());
+        }
+        if let Some(shield) = &self.shield {
+            entity.insert(shield.clone());
+        }
+
+        match &self.kind {
+            BodyKind::Player(player) => {
+                entity.insert(*player);
+                if let Some(game_textures) = game_textures {
+                    let ship = game_textures.players[player.0];
+                    entity.with_children(|parent| spawn_engine_fire(parent, game_textures, ship));
+                }
+            }
+            BodyKind::Enemy(kind, formation) => {
+                entity.insert(Enemy).insert(*kind).insert(formation.clone());
+            }
+            BodyKind::Laser(Some(from_player)) => {
+                entity.insert(Laser).insert(*from_player);
+            }
+            BodyKind::Laser(None) => {
+                entity.insert(Laser).insert(FromEnemy);
+            }
+        }
+    }
+}
+
+/// Players, enemies and lasers, in archetype order.
+fn body_entities(world: &World) -> Vec<Entity> {
+    let ids: Vec<ComponentId> = [
+        TypeId::of::<Player>(),
+        TypeId::of::<Enemy>(),
+        TypeId::of::<Laser>(),
+    ]
+    .into_iter()
+    .filter_map(|type_id| world.components().get_id(type_id))
+    .collect();
+
+    world
+        .archetypes()
+        .iter()
+        .filter(|archetype| ids.iter().any(|id| archetype.contains(*id)))
+        .flat_map(|archetype| archetype.entities().iter().copied())
+        .collect()
+}
+
+fn clear_events<T: Send + Sync + 'static>(world: &mut World) {
+    if let Some(mut events) = world.get_resource_mut::<Events<T>>() {
+        events.clear();
+    }
+}
+
+impl Simulation for World {
+    type State = GameSnapshot;
+
+    fn save(&self) -> GameSnapshot {
+        GameSnapshot {
+            tick: self.resource::<Online>().tick,
+            screen: *self.resource::<Screen>(),
+            players: self.resource::<Players>().clone(),
+            actions: self.resource::<PlayerActions>().clone(),
+            enemy_count: self.resource::<EnemyCount>().clone(),
+            wave: self.resource::<Wave>().clone(),
+            wave_spawned: self.resource::<WaveSpawned>().clone(),
+            rng: self.resource::<GameRng>().clone(),
+            clock: *self.resource::<GameClock>(),
+            formation_maker: self.resource::<FormationMaker>().clone(),
+            time_scale: self.resource::<TimeScale>().clone(),
+            paused: self.resource::<Paused>().clone(),
+            bodies: body_entities(self)
+                .into_iter()
+                .filter_map(|entity| Body::of(self, entity))
+                .collect(),
+        }
+    }
+
+    fn load(&mut self, state: &GameSnapshot) {
+        for entity in body_entities(self) {
+            self.entity_mut(entity).despawn_recursive();
+        }
+        // whatever the undone ticks sent never happened
+        clear_events::<LaserFired>(self);
+        clear_events::<EnemyDestroyed>(self);
+        clear_events::<PlayerHit>(self);
+        clear_events::<WaveCleared>(self);
+        clear_events::<PowerUpCollected>(self);
+        clear_events::<ShieldDown>(self);
+        clear_events::<CollisionEvent>(self);
+
+        self.resource_mut::<Online>().tick = state.tick;
+        self.insert_resource(state.screen);
+        self.insert_resource(state.players.clone());
+        self.insert_resource(state.actions.clone());
+        self.insert_resource(state.enemy_count.clone());
+        self.insert_resource(state.wave.clone());
+        self.insert_resource(state.wave_spawned.clone());
+        self.insert_resource(state.rng.clone());
+        self.insert_resource(state.clock);
+        self.insert_resource(state.formation_maker.clone());
+        self.insert_resource(state.time_scale.clone());
+        self.insert_resource(state.paused.clone());
+
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, self);
+        let game_textures = self.get_resource::<GameTextures>();
+        for body in &state.bodies {
+            body.spawn(&mut commands, game_textures);
+        }
+        queue.apply(self);
+    }
+
+    fn advance(&mut self, inputs: [ActionFrame; MAX_PLAYERS]) {
+        let mut online = self.resource_mut::<Online>();
+        online.resimulating = online.tick < online.played;
+
+        run_tick(self, inputs);
+
+        let mut online = self.resource_mut::<Online>();
+        online.tick += 1;
+        online.played = online.played.max(online.tick);
+    }
+}
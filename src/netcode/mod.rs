@@ -0,0 +1,10 @@
+pub mod game;
+pub mod protocol;
+pub mod session;
+pub mod transport;
+
+pub use self::{
+    game::{start, GameSnapshot, Netplay, Online},
+    session::{RollbackSession, SessionConfig, SessionStats, Simulation},
+    transport::{LinkShim, Transport, UdpTransport},
+};
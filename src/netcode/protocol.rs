@@ -0,0 +1,108 @@
+use bevy::math::Vec2;
+
+use crate::controls::{ActionFrame, ActionSet};
+
+const MAGIC: u8 = 0x5e;
+const HEADER_LEN: usize = 10;
+const FRAME_LEN: usize = 9;
+/// inputs per packet, older unacked ones are sent again next tick
+pub const MAX_FRAMES: usize = 64;
+
+/// The only packet: the sender's inputs from tick `start` on, plus how many
+/// of the receiver's inputs the sender has confirmed so far.
+///
+/// Every packet repeats all unacked inputs, so a lost packet costs nothing
+/// but latency.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InputPacket {
+    pub ack: u32,
+    pub start: u32,
+    pub frames: Vec<ActionFrame>,
+}
+
+impl InputPacket {
+    /// `magic | ack u32 | start u32 | count u8 | (held u8 | x f32 | y f32)*`,
+    /// little endian.
+    pub fn encode(&self) -> Vec<u8> {
+        let frames = &self.frames[..self.frames.len().min(MAX_FRAMES)];
+        let mut bytes = Vec::with_capacity(HEADER_LEN + frames.len() * FRAME_LEN);
+        bytes.push(MAGIC);
+        bytes.extend(self.ack.to_le_bytes());
+        bytes.extend(self.start.to_le_bytes());
+        bytes.push(frames.len() as u8);
+        for frame in frames {
+            bytes.push(frame.held.0);
+            bytes.extend(frame.stick.x.to_le_bytes());
+            bytes.extend(frame.stick.y.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() < HEADER_LEN || bytes[0] != MAGIC {
+            return Err("not an input packet".to_string());
+        }
+        let u32_at = |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+        let f32_at = |i: usize| f32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+
+        let count = bytes[9] as usize;
+        if bytes.len() != HEADER_LEN + count * FRAME_LEN {
+            return Err(format!(
+                "input packet of {} bytes for {count} frames",
+                bytes.len()
+            ));
+        }
+
+        let frames = (0..count)
+            .map(|n| {
+                let i = HEADER_LEN + n * FRAME_LEN;
+                ActionFrame {
+                    held: ActionSet(bytes[i]),
+                    stick: Vec2::new(f32_at(i + 1), f32_at(i + 5)),
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            ack: u32_at(1),
+            start: u32_at(5),
+            frames,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controls::Action;
+
+    #[test]
+    fn round_trips_through_bytes() {
+        let packet = InputPacket {
+            ack: 7,
+            start: 300,
+            frames: vec![
+                ActionFrame::default(),
+                ActionFrame {
+                    held: [Action::Fire, Action::MoveLeft].into_iter().collect(),
+                    stick: Vec2::new(-0.25, 1.),
+                },
+            ],
+        };
+
+        assert_eq!(InputPacket::decode(&packet.encode()), Ok(packet));
+    }
+
+    #[test]
+    fn rejects_garbage_and_truncated_packets() {
+        let bytes = InputPacket {
+            ack: 0,
+            start: 0,
+            frames: vec![ActionFrame::default()],
+        }
+        .encode();
+
+        assert!(InputPacket::decode(b"hello").is_err());
+        assert!(InputPacket::decode(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
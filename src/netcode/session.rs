@@ -0,0 +1,213 @@
+use std::collections::BTreeMap;
+
+use crate::{controls::ActionFrame, MAX_PLAYERS};
+
+use super::{
+    protocol::{InputPacket, MAX_FRAMES},
+    transport::Transport,
+};
+
+/// A game that can be snapshot, restored and stepped one tick at a time.
+///
+/// `advance` must be deterministic: the same state and inputs always give
+/// the same next state, on both peers.
+pub trait Simulation {
+    type State: Clone;
+
+    fn save(&self) -> Self::State;
+    fn load(&mut self, state: &Self::State);
+    fn advance(&mut self, inputs: [ActionFrame; MAX_PLAYERS]);
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SessionConfig {
+    /// which `Player` number this peer controls, the other one is remote
+    pub local_player: usize,
+    /// ticks between reading a local input and playing it, trading a little
+    /// latency for fewer rollbacks
+    pub input_delay: u32,
+    /// ticks the simulation may run ahead of the remote inputs before it
+    /// waits for them
+    pub max_prediction: u32,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            local_player: 0,
+            input_delay: 2,
+            max_prediction: 8,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SessionStats {
+    pub rollbacks: u32,
+    /// ticks simulated again because of rollbacks
+    pub resimulated: u32,
+    /// frames skipped waiting for the remote peer
+    pub stalls: u32,
+}
+
+/// Two-player rollback session, GGRS style.
+///
+/// Each frame the local input is sent to the peer and the simulation runs
+/// on, predicting that the remote player still holds their last confirmed
+/// input. When the real input arrives and differs from the prediction, the
+/// simulation is rolled back to that tick and replayed with it.
+pub struct RollbackSession<T: Transport, S: Simulation> {
+    config: SessionConfig,
+    transport: T,
+    /// next tick to simulate
+    tick: u32,
+    /// local inputs by tick, `input_delay` ticks ahead of `tick`
+    local: Vec<ActionFrame>,
+    /// confirmed remote inputs by tick
+    remote: Vec<ActionFrame>,
+    /// remote inputs guessed for unconfirmed ticks
+    predicted: BTreeMap<u32, ActionFrame>,
+    /// state at the start of each unconfirmed tick
+    states: BTreeMap<u32, S::State>,
+    /// local inputs the peer has confirmed
+    peer_ack: u32,
+    stats: SessionStats,
+}
+
+impl<T: Transport, S: Simulation> RollbackSession<T, S> {
+    pub fn new(config: SessionConfig, transport: T) -> Self {
+        Self {
+            local: vec![ActionFrame::default(); config.input_delay as usize],
+            config,
+            transport,
+            tick: 0,
+            remote: Vec::new(),
+            predicted: BTreeMap::new(),
+            states: BTreeMap::new(),
+            peer_ack: 0,
+            stats: SessionStats::default(),
+        }
+    }
+
+    /// `Player` number controlled on this peer.
+    pub fn local_player(&self) -> usize {
+        self.config.local_player
+    }
+
+    /// Ticks simulated so far, some of them maybe on predicted input.
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+
+    /// Ticks simulated with both players' real inputs.
+    pub fn confirmed_tick(&self) -> u32 {
+        self.tick.min(self.remote.len() as u32)
+    }
+
+    pub fn stats(&self) -> SessionStats {
+        self.stats
+    }
+
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    /// Run one frame: exchange inputs, roll back if a prediction was wrong,
+    /// then advance `sim` by a tick with `input` from the local player.
+    /// Returns false when it had to wait for the peer instead.
+    pub fn advance_frame(&mut self, sim: &mut S, input: ActionFrame) -> bool {
+        self.receive();
+        self.roll_back(sim);
+
+        let ahead = self.tick - self.confirmed_tick();
+        let advanced = if ahead < self.config.max_prediction {
+            self.local.push(input);
+            self.simulate(sim);
+            true
+        } else {
+            self.stats.stalls += 1;
+            false
+        };
+
+        self.send();
+        advanced
+    }
+
+    fn receive(&mut self) {
+        while let Some(bytes) = self.transport.recv() {
+            let packet = match InputPacket::decode(&bytes) {
+                Ok(packet) => packet,
+                Err(_) => continue,
+            };
+            self.peer_ack = self.peer_ack.max(packet.ack);
+
+            // only the inputs right after the ones we have, packets may be
+            // old or out of order
+            let known = self.remote.len() as u32;
+            if packet.start > known {
+                continue;
+            }
+            let new = packet.frames.iter().skip((known - packet.start) as usize);
+            self.remote.extend(new);
+        }
+    }
+
+    /// Replay from the first tick whose prediction turned out wrong.
+    fn roll_back(&mut self, sim: &mut S) {
+        let confirmed = self.confirmed_tick();
+        let mut first_wrong = None;
+        while let Some((&tick, &guess)) = self.predicted.iter().next() {
+            if tick >= confirmed {
+                break;
+            }
+            self.predicted.remove(&tick);
+            if first_wrong.is_none() && guess != self.remote[tick as usize] {
+                first_wrong = Some(tick);
+            }
+        }
+
+        if let Some(from) = first_wrong {
+            let end = self.tick;
+            sim.load(&self.states[&from]);
+            self.tick = from;
+            while self.tick < end {
+                self.simulate(sim);
+                self.stats.resimulated += 1;
+            }
+            self.stats.rollbacks += 1;
+        }
+
+        // nothing before a confirmed tick can be rolled back to again
+        let confirmed = self.confirmed_tick();
+        self.states = self.states.split_off(&confirmed);
+    }
+
+    fn simulate(&mut self, sim: &mut S) {
+        let tick = self.tick;
+        let remote = match self.remote.get(tick as usize) {
+            Some(frame) => *frame,
+            None => {
+                let guess = self.remote.last().copied().unwrap_or_default();
+                self.predicted.insert(tick, guess);
+                self.states.insert(tick, sim.save());
+                guess
+            }
+        };
+
+        let mut inputs = [remote; MAX_PLAYERS];
+        inputs[self.config.local_player] = self.local[tick as usize];
+        sim.advance(inputs);
+        self.tick += 1;
+    }
+
+    fn send(&mut self) {
+        let start = self.peer_ack.min(self.local.len() as u32);
+        let end = self.local.len().min(start as usize + MAX_FRAMES);
+        let packet = InputPacket {
+            ack: self.remote.len() as u32,
+            start,
+            frames: self.local[start as usize..end].to_vec(),
+        };
+        self.transport.send(&packet.encode());
+    }
+}
@@ -0,0 +1,224 @@
+use std::{
+    collections::VecDeque,
+    io::{self, ErrorKind},
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
+    time::{Duration, Instant},
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Largest datagram we ever expect, well above a full `InputPacket`
+const MAX_DATAGRAM: usize = 1024;
+
+/// Unreliable, unordered datagrams to and from the other peer
+pub trait Transport {
+    fn send(&mut self, bytes: &[u8]);
+
+    /// Next datagram that has arrived, if any. Never blocks.
+    fn recv(&mut self) -> Option<Vec<u8>>;
+}
+
+/// Non-blocking UDP socket talking to a single peer
+pub struct UdpTransport {
+    socket: UdpSocket,
+    /// `None` until the first datagram when listening
+    peer: Option<SocketAddr>,
+}
+
+impl UdpTransport {
+    pub fn bind(local: impl ToSocketAddrs, peer: impl ToSocketAddrs) -> io::Result<Self> {
+        let peer = peer
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "no peer address"))?;
+        let socket = UdpSocket::bind(local)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            peer: Some(peer),
+        })
+    }
+
+    /// Wait for a peer on `local`: whoever sends first becomes the peer,
+    /// nothing is sent before that.
+    pub fn listen(local: impl ToSocketAddrs) -> io::Result<Self> {
+        let socket = UdpSocket::bind(local)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self { socket, peer: None })
+    }
+
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.peer
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+}
+
+impl Transport for UdpTransport {
+    fn send(&mut self, bytes: &[u8]) {
+        // best effort, the next tick resends anything lost
+        if let Some(peer) = self.peer {
+            let _ = self.socket.send_to(bytes, peer);
+        }
+    }
+
+    fn recv(&mut self) -> Option<Vec<u8>> {
+        let mut buf = [0; MAX_DATAGRAM];
+        loop {
+            match self.socket.recv_from(&mut buf) {
+                Ok((len, from)) => match self.peer {
+                    Some(peer) if peer != from => continue,
+                    _ => {
+                        self.peer = Some(from);
+                        return Some(buf[..len].to_vec());
+                    }
+                },
+                // the peer is not listening yet, its ICMP reply lands here
+                Err(err) if err.kind() == ErrorKind::ConnectionRefused => continue,
+                Err(_) => return None,
+            }
+        }
+    }
+}
+
+/// Bad network conditions on the way out of a `Transport`, to test netplay
+/// over loopback
+pub struct LinkShim<T> {
+    inner: T,
+    pub latency: Duration,
+    /// extra random delay, up to this much, so packets also arrive out of order
+    pub jitter: Duration,
+    /// chance in 0..=1 of dropping each packet
+    pub loss: f64,
+    rng: StdRng,
+    in_flight: VecDeque<(Instant, Vec<u8>)>,
+}
+
+impl<T: Transport> LinkShim<T> {
+    /// `seed` makes the dropped packets the same on every run.
+    pub fn new(inner: T, latency: Duration, loss: f64, seed: u64) -> Self {
+        Self {
+            inner,
+            latency,
+            jitter: Duration::ZERO,
+            loss,
+            rng: StdRng::seed_from_u64(seed),
+            in_flight: VecDeque::new(),
+        }
+    }
+
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Hand the packets that are due over to the real transport.
+    fn flush(&mut self) {
+        let now = Instant::now();
+        let (due, waiting) = self.in_flight.drain(..).partition(|(at, _)| *at <= now);
+        self.in_flight = waiting;
+        for (_, bytes) in Vec::from(due) {
+            self.inner.send(&bytes);
+        }
+    }
+}
+
+impl<T: Transport> Transport for LinkShim<T> {
+    fn send(&mut self, bytes: &[u8]) {
+        if self.rng.gen_bool(self.loss.clamp(0., 1.)) {
+            return;
+        }
+        let jitter = self.jitter.mul_f64(self.rng.gen());
+        self.in_flight
+            .push_back((Instant::now() + self.latency + jitter, bytes.to_vec()));
+        self.flush();
+    }
+
+    fn recv(&mut self) -> Option<Vec<u8>> {
+        self.flush();
+        self.inner.recv()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair() -> (UdpTransport, UdpTransport) {
+        let a = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let b = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let (a_addr, b_addr) = (a.local_addr().unwrap(), b.local_addr().unwrap());
+        drop((a, b));
+        (
+            UdpTransport::bind(a_addr, b_addr).unwrap(),
+            UdpTransport::bind(b_addr, a_addr).unwrap(),
+        )
+    }
+
+    fn recv_within(transport: &mut impl Transport, timeout: Duration) -> Option<Vec<u8>> {
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            if let Some(bytes) = transport.recv() {
+                return Some(bytes);
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        None
+    }
+
+    #[test]
+    fn udp_peers_talk_over_loopback() {
+        let (mut a, mut b) = pair();
+
+        a.send(b"ping");
+
+        assert_eq!(
+            recv_within(&mut b, Duration::from_secs(1)),
+            Some(b"ping".to_vec())
+        );
+        assert_eq!(b.recv(), None);
+    }
+
+    #[test]
+    fn listener_answers_whoever_calls_first() {
+        let mut host = UdpTransport::listen("127.0.0.1:0").unwrap();
+        let mut guest = UdpTransport::bind("127.0.0.1:0", host.local_addr().unwrap()).unwrap();
+        assert_eq!(host.peer_addr(), None);
+
+        guest.send(b"hello");
+        assert_eq!(
+            recv_within(&mut host, Duration::from_secs(1)),
+            Some(b"hello".to_vec())
+        );
+        assert_eq!(host.peer_addr(), Some(guest.local_addr().unwrap()));
+
+        host.send(b"welcome");
+        assert_eq!(
+            recv_within(&mut guest, Duration::from_secs(1)),
+            Some(b"welcome".to_vec())
+        );
+    }
+
+    #[test]
+    fn shim_delays_and_drops() {
+        let (a, mut b) = pair();
+        let mut a = LinkShim::new(a, Duration::from_millis(30), 0., 1);
+
+        let sent = Instant::now();
+        a.send(b"late");
+        let arrived = (0..1000).any(|_| {
+            // polling is what lets the shim send it on
+            a.recv();
+            recv_within(&mut b, Duration::from_millis(1)).is_some()
+        });
+        assert!(arrived);
+        assert!(sent.elapsed() >= Duration::from_millis(30));
+
+        a.loss = 1.;
+        a.latency = Duration::ZERO;
+        a.send(b"lost");
+        assert_eq!(recv_within(&mut b, Duration::from_millis(50)), None);
+    }
+}
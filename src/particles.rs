@@ -0,0 +1,345 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use bevy::prelude::*;
+use rand::{thread_rng, Rng};
+
+use crate::{
+    events::{EnemyDestroyed, PlayerHit},
+    netcode::Online,
+    GameTextures, TimeScale,
+};
+
+/// Cap on live particles, emitters skip particles past it
+pub const MAX_PARTICLES: usize = 600;
+const SPARK_COUNT: usize = 14;
+const DEBRIS_COUNT: usize = 6;
+/// in front of the ships and explosions
+const PARTICLE_Z: f32 = 20.;
+
+/// Look of the particles an emitter spawns
+#[derive(Clone, Debug)]
+pub struct ParticleEffect {
+    /// atlas indices of the sprite sheet, one picked at random per particle
+    pub sprites: Vec<usize>,
+    /// seconds
+    pub lifetime: f32,
+    /// middle of the velocity cone, radians from +x
+    pub direction: f32,
+    /// half-angle of the velocity cone, radians
+    pub spread: f32,
+    /// pixels per second, picked at random between the two
+    pub speed: (f32, f32),
+    /// at birth and at the end of life, faded in between
+    pub colour: (Color, Color),
+    /// width and height in pixels, at birth and at the end of life
+    pub size: (f32, f32),
+}
+
+impl ParticleEffect {
+    /// Flickering exhaust, pointing down from a ship's engine.
+    pub fn engine_trail(game_textures: &GameTextures) -> Self {
+        Self {
+            sprites: game_textures.sparks.map(|sprite| sprite.index).to_vec(),
+            lifetime: 0.35,
+            direction: -FRAC_PI_2,
+            spread: 0.25,
+            speed: (80., 160.),
+            colour: (Color::rgba(1., 0.8, 0.3, 0.8), Color::rgba(1., 0.2, 0., 0.)),
+            size: (8., 2.),
+        }
+    }
+
+    /// Quick bright sparks in every direction, where a laser hit.
+    pub fn sparks(game_textures: &GameTextures) -> Self {
+        Self {
+            sprites: game_textures.sparks.map(|sprite| sprite.index).to_vec(),
+            lifetime: 0.3,
+            direction: 0.,
+            spread: PI,
+            speed: (150., 400.),
+            colour: (Color::rgb(1., 1., 0.7), Color::rgba(1., 0.5, 0.1, 0.)),
+            size: (10., 3.),
+        }
+    }
+
+    /// Slow tumbling pieces of a destroyed enemy.
+    pub fn debris(game_textures: &GameTextures) -> Self {
+        Self {
+            sprites: game_textures.debris.map(|sprite| sprite.index).to_vec(),
+            lifetime: 0.9,
+            direction: 0.,
+            spread: PI,
+            speed: (40., 140.),
+            colour: (Color::rgb(0.7, 0.7, 0.7), Color::rgba(0.4, 0.4, 0.4, 0.)),
+            size: (12., 6.),
+        }
+    }
+}
+
+/// Component - spawns particles from the entity's position
+#[derive(Component, Clone, Debug)]
+pub struct ParticleEmitter {
+    pub effect: ParticleEffect,
+    /// particles per second
+    pub rate: f32,
+    /// particles spawned at once on the first update
+    pub burst: usize,
+    /// seconds before the emitter entity is despawned, `None` for never
+    pub duration: Option<f32>,
+    elapsed: f32,
+    /// fraction of a particle owed by the rate
+    pending: f32,
+}
+
+impl ParticleEmitter {
+    /// Emit `rate` particles per second, for as long as the entity lives.
+    pub fn stream(effect: ParticleEffect, rate: f32) -> Self {
+        Self {
+            effect,
+            rate,
+            burst: 0,
+            duration: None,
+            elapsed: 0.,
+            pending: 0.,
+        }
+    }
+
+    /// Emit `count` particles at once, then despawn.
+    pub fn burst(effect: ParticleEffect, count: usize) -> Self {
+        Self {
+            burst: count,
+            duration: Some(0.),
+            ..Self::stream(effect, 0.)
+        }
+    }
+
+    /// Particles due after `seconds` more.
+    fn due(&mut self, seconds: f32) -> usize {
+        let burst = if self.elapsed == 0. { self.burst } else { 0 };
+        self.elapsed += seconds;
+        self.pending += self.rate * seconds;
+        let streamed = self.pending.floor();
+        self.pending -= streamed;
+        burst + streamed as usize
+    }
+
+    fn is_done(&self) -> bool {
+        self.duration
+            .is_some_and(|duration| self.elapsed > 0. && self.elapsed >= duration)
+    }
+}
+
+/// Component - one particle, moving in a straight line until its lifetime
+/// is over
+#[derive(Component, Clone, Debug)]
+pub struct Particle {
+    pub velocity: Vec2,
+    pub age: f32,
+    pub lifetime: f32,
+    pub colour: (Color, Color),
+    pub size: (f32, f32),
+}
+
+impl Particle {
+    /// Share of the lifetime gone, 0 to 1.
+    pub fn progress(&self) -> f32 {
+        (self.age / self.lifetime).clamp(0., 1.)
+    }
+}
+
+/// Plugin - CPU particles: engine trails, laser sparks and enemy debris
+pub struct ParticlePlugin;
+
+impl Plugin for ParticlePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TimeScale>()
+            .add_event::<EnemyDestroyed>()
+            .add_event::<PlayerHit>()
+            .add_system_to_stage(CoreStage::PostUpdate, impact_particles_system)
+            .add_system(particle_emitter_system)
+            .add_system(particle_system);
+    }
+}
+
+/// Sparks on every laser hit, and debris where an enemy was.
+fn impact_particles_system(
+    mut commands: Commands,
+    game_textures: Option<Res<GameTextures>>,
+    mut enemy_destroyed_events: EventReader<EnemyDestroyed>,
+    online: Option<Res<Online>>,
+    mut player_hit_events: EventReader<PlayerHit>,
+) {
+    // a replayed tick's sparks played the first time round
+    if online.is_some_and(|online| online.resimulating) {
+        enemy_destroyed_events.clear();
+        player_hit_events.clear();
+        return;
+    }
+
+    let game_textures = match game_textures {
+        Some(game_textures) => game_textures,
+        None => return,
+    };
+    let spawn = |commands: &mut Commands, position: Vec3, emitter: ParticleEmitter| {
+        // placed already, the emitter runs before transforms propagate
+        let transform = Transform::from_translation(position);
+        commands
+            .spawn_bundle(TransformBundle {
+                local: transform,
+                global: transform.into(),
+            })
+            .insert(emitter);
+    };
+
+    for event in enemy_destroyed_events.iter() {
+        let sparks = ParticleEffect::sparks(&game_textures);
+        let debris = ParticleEffect::debris(&game_textures);
+        spawn(
+            &mut commands,
+            event.position,
+            ParticleEmitter::burst(sparks, SPARK_COUNT),
+        );
+        spawn(
+            &mut commands,
+            event.position,
+            ParticleEmitter::burst(debris, DEBRIS_COUNT),
+        );
+    }
+    for event in player_hit_events.iter() {
+        let sparks = ParticleEffect::sparks(&game_textures);
+        spawn(
+            &mut commands,
+            event.position,
+            ParticleEmitter::burst(sparks, SPARK_COUNT),
+        );
+    }
+}
+
+fn particle_emitter_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    game_textures: Option<Res<GameTextures>>,
+    particle_query: Query<(), With<Particle>>,
+    mut emitter_query: Query<(Entity, &mut ParticleEmitter, &GlobalTransform)>,
+) {
+    let game_textures = match game_textures {
+        Some(game_textures) => game_textures,
+        None => return,
+    };
+    let seconds = time.delta_seconds() * time_scale.0.max(0.);
+    if seconds <= 0. {
+        return;
+    }
+    let mut rng = thread_rng();
+    let mut room = MAX_PARTICLES.saturating_sub(particle_query.iter().count());
+
+    for (entity, mut emitter, global_tf) in emitter_query.iter_mut() {
+        let count = emitter.due(seconds).min(room);
+        room -= count;
+
+        let effect = &emitter.effect;
+        let origin = global_tf.translation().truncate().extend(PARTICLE_Z);
+        for _ in 0..count {
+            let sprite = match effect.sprites.len() {
+                0 => 0,
+                len => effect.sprites[rng.gen_range(0..len)],
+            };
+            let angle = effect.direction + rng.gen_range(-1_f32..=1.) * effect.spread;
+            let speed = rng.gen_range(effect.speed.0..=effect.speed.1.max(effect.speed.0));
+            commands
+                .spawn_bundle(SpriteSheetBundle {
+                    sprite: TextureAtlasSprite {
+                        index: sprite,
+                        color: effect.colour.0,
+                        custom_size: Some(Vec2::splat(effect.size.0)),
+                        ..Default::default()
+                    },
+                    texture_atlas: game_textures.sheet.clone(),
+                    transform: Transform::from_translation(origin),
+                    ..Default::default()
+                })
+                .insert(Particle {
+                    velocity: Vec2::new(angle.cos(), angle.sin()) * speed,
+                    age: 0.,
+                    lifetime: effect.lifetime,
+                    colour: effect.colour,
+                    size: effect.size,
+                });
+        }
+
+        if emitter.is_done() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn particle_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mut query: Query<(
+        Entity,
+        &mut Particle,
+        &mut Transform,
+        &mut TextureAtlasSprite,
+    )>,
+) {
+    let seconds = time.delta_seconds() * time_scale.0.max(0.);
+
+    for (entity, mut particle, mut transform, mut sprite) in query.iter_mut() {
+        particle.age += seconds;
+        if particle.age >= particle.lifetime {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        transform.translation += (particle.velocity * seconds).extend(0.);
+        let t = particle.progress();
+        let (start, end) = particle.colour;
+        sprite.color = Color::rgba(
+            start.r() + (end.r() - start.r()) * t,
+            start.g() + (end.g() - start.g()) * t,
+            start.b() + (end.b() - start.b()) * t,
+            start.a() + (end.a() - start.a()) * t,
+        );
+        let size = particle.size.0 + (particle.size.1 - particle.size.0) * t;
+        sprite.custom_size = Some(Vec2::splat(size));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn effect() -> ParticleEffect {
+        ParticleEffect {
+            sprites: vec![1],
+            lifetime: 1.,
+            direction: 0.,
+            spread: 0.,
+            speed: (1., 1.),
+            colour: (Color::WHITE, Color::NONE),
+            size: (1., 1.),
+        }
+    }
+
+    #[test]
+    fn streams_at_its_rate() {
+        let mut emitter = ParticleEmitter::stream(effect(), 10.);
+
+        let spawned: usize = (0..20).map(|_| emitter.due(0.025)).sum();
+        assert_eq!(spawned, 5);
+        assert!(!emitter.is_done());
+    }
+
+    #[test]
+    fn bursts_once_then_is_done() {
+        let mut emitter = ParticleEmitter::burst(effect(), 12);
+        assert!(!emitter.is_done());
+
+        assert_eq!(emitter.due(0.016), 12);
+        assert!(emitter.is_done());
+        assert_eq!(emitter.due(0.016), 0);
+    }
+}
@@ -1,109 +1,408 @@
+use serde::{Deserialize, Serialize};
+
 use crate::{
-    components::{FromPlayer, Laser, Movable, Player, SpriteSize, Velocity},
-    movable_system, GameTextures, PlayerState, WinSize, BASE_SPEED, PLAYER_LASER_SIZE,
-    PLAYER_RESPAWN_DELAY, PLAYER_SIZE, SPRITE_SCALE, TIME_STEP,
+    animation::{AnimationClip, PlayMode, SpriteAnimation},
+    atlas::SheetSprite,
+    collision::{
+        shape::{AlphaMaskSource, HitShape},
+        Collider, CollisionDetection, Layers,
+    },
+    components::{
+        FromPlayer, Laser, Movable, Player, PreviousPosition, Shield, SpriteSize, Velocity,
+    },
+    controls::{Action, PlayerActions},
+    events::{
+        EnemyDestroyed, LaserFired, PlayerHit, PowerUpCollected, PowerUpKind, ShieldDown, Shooter,
+    },
+    game_clock_step, game_clock_system, movable_system,
+    particles::{ParticleEffect, ParticleEmitter},
+    GameClock, GameConfig, GameTextures, Movement, Paused, Players, TimeScale, WinSize, ENEMY_POINTS,
+    ENGINE_FIRE_FPS, ENGINE_TRAIL_RATE, MAX_PLAYERS, PLAYER_ACCELERATION, PLAYER_DECELERATION,
+    SHIELD_DURATION, SPRITE_SCALE, TIME_STEP,
 };
-use bevy::{prelude::*, time::FixedTimestep};
+use bevy::prelude::*;
+
+/// Ship colours of the `playerShipN_<colour>.png` sprites
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShipColour {
+    Blue,
+    Green,
+    Orange,
+    Red,
+}
+
+impl ShipColour {
+    pub const ALL: [ShipColour; 4] = [
+        ShipColour::Blue,
+        ShipColour::Green,
+        ShipColour::Orange,
+        ShipColour::Red,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ShipColour::Blue => "blue",
+            ShipColour::Green => "green",
+            ShipColour::Orange => "orange",
+            ShipColour::Red => "red",
+        }
+    }
+
+    /// Light shade of the colour, to tint the ship's explosion.
+    pub fn tint(&self) -> Color {
+        match self {
+            ShipColour::Blue => Color::rgb(0.6, 0.8, 1.),
+            ShipColour::Green => Color::rgb(0.7, 1., 0.7),
+            ShipColour::Orange => Color::rgb(1., 0.85, 0.6),
+            ShipColour::Red => Color::rgb(1., 0.7, 0.7),
+        }
+    }
+}
+
+/// Sprite sheet name of the ship of player `number`, each player flies their own model.
+pub fn player_ship_sprite(number: usize, colour: ShipColour) -> String {
+    format!("playerShip{}_{}.png", number + 1, colour.name())
+}
 
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(PlayerState::default())
+        app.init_resource::<Players>()
+            .init_resource::<GameConfig>()
+            .init_resource::<TimeScale>()
+            .init_resource::<Paused>()
+            .init_resource::<PlayerActions>()
+            .init_resource::<GameClock>()
+            .add_event::<LaserFired>()
+            .add_event::<PlayerHit>()
+            .add_event::<EnemyDestroyed>()
+            .add_event::<PowerUpCollected>()
+            .add_event::<ShieldDown>()
             .add_system_set(
                 SystemSet::new()
-                    .with_run_criteria(FixedTimestep::step(0.5))
+                    .with_run_criteria(game_clock_step(0.5))
                     .with_system(player_spawn_system),
             )
+            // before anything reads it this frame
+            .add_system_to_stage(CoreStage::First, game_clock_system)
             // .add_system(player_movement_system)
-            .add_system(movable_system)
-            .add_system(player_keyboard_event_system)
-            .add_system(player_fire_system);
+            .add_system(player_control_system.before(Movement))
+            .add_system(movable_system.label(Movement))
+            .add_system(
+                player_bounds_system
+                    .after(Movement)
+                    .before(CollisionDetection),
+            )
+            // from where the ship ended up this tick
+            .add_system(player_fire_system.after(CollisionDetection))
+            .add_system_to_stage(CoreStage::PostUpdate, player_hit_system)
+            .add_system_to_stage(CoreStage::PostUpdate, player_score_system)
+            .add_system_to_stage(CoreStage::PostUpdate, player_shield_system);
+    }
+}
+
+fn player_hit_system(
+    mut players: ResMut<Players>,
+    clock: Res<GameClock>,
+    mut player_hit_events: EventReader<PlayerHit>,
+) {
+    for event in player_hit_events.iter() {
+        let player_state = &mut players.0[event.number];
+        if player_state.alive {
+            player_state.shot(clock.seconds);
+        }
+    }
+}
+
+fn player_score_system(
+    mut players: ResMut<Players>,
+    mut enemy_destroyed_events: EventReader<EnemyDestroyed>,
+) {
+    for event in enemy_destroyed_events.iter() {
+        if let Some(number) = event.by_player {
+            players.0[number].score += ENEMY_POINTS;
+        }
+    }
+}
+
+/// Raise a `Shield` on shield power-ups, and drop it once it has lasted
+/// `SHIELD_DURATION`.
+fn player_shield_system(
+    mut commands: Commands,
+    clock: Res<GameClock>,
+    mut power_up_events: EventReader<PowerUpCollected>,
+    mut shield_down_events: EventWriter<ShieldDown>,
+    query: Query<(Entity, Option<&Shield>, &Transform), With<Player>>,
+) {
+    for (entity, shield, player_tf) in query.iter() {
+        if shield.is_some_and(|shield| clock.seconds >= shield.until) {
+            commands.entity(entity).remove::<Shield>();
+            shield_down_events.send(ShieldDown {
+                player: entity,
+                position: player_tf.translation,
+            });
+        }
+    }
+
+    for event in power_up_events.iter() {
+        // the player may have been shot in the meantime
+        if event.kind == PowerUpKind::Shield && query.contains(event.player) {
+            commands.entity(event.player).insert(Shield {
+                until: clock.seconds + SHIELD_DURATION,
+            });
+        }
     }
 }
 
 fn player_spawn_system(
     mut commands: Commands,
-    mut player_state: ResMut<PlayerState>,
-    time: Res<Time>,
+    mut players: ResMut<Players>,
+    clock: Res<GameClock>,
+    config: Res<GameConfig>,
     win_size: Res<WinSize>,
-    game_textures: Res<GameTextures>,
+    game_textures: Option<Res<GameTextures>>,
 ) {
-    let now = time.seconds_since_startup();
-    let last_shot = player_state.last_shot;
+    let game_textures = match game_textures {
+        Some(game_textures) => game_textures,
+        None => return,
+    };
+    let now = clock.seconds;
+    let count = config.players.clamp(1, MAX_PLAYERS);
+
+    for (number, player_state) in players.0.iter_mut().enumerate().take(count) {
+        let last_shot = player_state.last_shot;
+        let respawn_due = last_shot == -1. || now > last_shot + config.player_respawn_delay;
+        if player_state.alive || player_state.lives == 0 || !respawn_due {
+            continue;
+        }
 
-    if !player_state.alive && (last_shot == -1. || now > last_shot + PLAYER_RESPAWN_DELAY) {
-        // get player sprites
-        let bottom = -win_size.h / 2.;
+        // solo in the middle, co-op players a quarter in from each side
+        let x = if count == 1 {
+            0.
+        } else {
+            (number as f32 / (count - 1) as f32 - 0.5) * win_size.w / 2.
+        };
+        let sprite = game_textures.players[number];
+        let (_, bottom) = player_y_range(&win_size, sprite.size().into(), false);
+
+        // friendly fire is decided on hit, so player lasers always meet players
         commands
-            .spawn_bundle(SpriteBundle {
-                texture: game_textures.player.clone(),
-                transform: Transform {
-                    translation: Vec3::new(
-                        0.,
-                        bottom + PLAYER_SIZE.1 / 2. * SPRITE_SCALE + 5.,
-                        10.0,
-                    ),
+            .spawn_bundle(game_textures.bundle(
+                &sprite,
+                Transform {
+                    translation: Vec3::new(x, bottom, 10.0),
                     scale: Vec3::new(SPRITE_SCALE, SPRITE_SCALE, 1.),
                     ..Default::default()
                 },
-                ..Default::default()
+            ))
+            .insert(Player(number))
+            .insert(SpriteSize(sprite.size()))
+            .insert(Collider::new(
+                Layers::PLAYER,
+                Layers::ENEMY_LASER | Layers::PLAYER_LASER,
+            ))
+            .insert(AlphaMaskSource {
+                image: game_textures.sheet_image.clone(),
+                sprite,
             })
-            .insert(Player)
-            .insert(SpriteSize::from(PLAYER_SIZE))
             .insert(Movable {
                 auto_despawn: false,
             })
-            .insert(Velocity { x: 0.0, y: 0.0 });
+            .insert(Velocity { x: 0.0, y: 0.0 })
+            .with_children(|parent| spawn_engine_fire(parent, &game_textures, sprite));
 
         player_state.spawned();
     }
 }
 
+/// Engine flame under a player `ship`, as its child.
+pub fn spawn_engine_fire(
+    parent: &mut ChildBuilder,
+    game_textures: &GameTextures,
+    ship: SheetSprite,
+) {
+    // in sprite pixels of the ship
+    let fire = game_textures.engine_fire[0];
+    let y = -(ship.size().y + fire.size().y) / 2. + 4.;
+    let frames = game_textures.engine_fire.map(|frame| frame.index);
+    parent
+        .spawn_bundle(game_textures.bundle(&fire, Transform::from_xyz(0., y, -0.1)))
+        .insert(SpriteAnimation::new(
+            AnimationClip::new(frames, ENGINE_FIRE_FPS).with_mode(PlayMode::PingPong),
+        ))
+        .insert(ParticleEmitter::stream(
+            ParticleEffect::engine_trail(game_textures),
+            ENGINE_TRAIL_RATE,
+        ));
+}
+
 fn player_fire_system(
     mut commands: Commands,
-    kb: Res<Input<KeyCode>>,
-    game_textures: Res<GameTextures>,
-    query: Query<&Transform, With<Player>>,
+    actions: Res<PlayerActions>,
+    paused: Res<Paused>,
+    game_textures: Option<Res<GameTextures>>,
+    mut laser_fired_events: EventWriter<LaserFired>,
+    query: Query<(&Player, &Transform, &SpriteSize)>,
 ) {
-    if let Ok(player_tf) = query.get_single() {
-        if kb.just_pressed(KeyCode::Space) {
-            let (x, y) = (player_tf.translation.x, player_tf.translation.y);
-            let x_offset: f32 = PLAYER_SIZE.0 / 2. * SPRITE_SCALE - 3.;
-            let mut spawn_laser = |x_offset: f32| {
-                commands
-                    .spawn_bundle(SpriteBundle {
-                        texture: game_textures.player_laser.clone(),
-                        transform: Transform {
-                            translation: Vec3::new(x + x_offset, y + 15., 0.),
-                            scale: Vec3::new(SPRITE_SCALE, SPRITE_SCALE, 1.),
-                            ..Default::default()
-                        },
-                        ..Default::default()
-                    })
-                    .insert(Laser)
-                    .insert(FromPlayer)
-                    .insert(SpriteSize::from(PLAYER_LASER_SIZE))
-                    .insert(Movable { auto_despawn: true })
-                    .insert(Velocity { x: 0.0, y: 1.0 });
-            };
-            spawn_laser(x_offset);
-            spawn_laser(-x_offset);
-        }
+    let game_textures = match game_textures {
+        Some(game_textures) if !paused.paused => game_textures,
+        _ => return,
     };
+    let laser = game_textures.player_laser;
+
+    for (player, player_tf, size) in query.iter() {
+        if !actions.0[player.0].just_pressed(Action::Fire) {
+            continue;
+        }
+
+        let (x, y) = (player_tf.translation.x, player_tf.translation.y);
+        let x_offset: f32 = size.0.x / 2. * SPRITE_SCALE - 3.;
+        let mut spawn_laser = |x_offset: f32| {
+            let translation = Vec3::new(x + x_offset, y + 15., 5.);
+            commands
+                .spawn_bundle(game_textures.bundle(
+                    &laser,
+                    Transform {
+                        translation,
+                        scale: Vec3::new(SPRITE_SCALE, SPRITE_SCALE, 1.),
+                        ..Default::default()
+                    },
+                ))
+                .insert(Laser)
+                .insert(FromPlayer(player.0))
+                .insert(SpriteSize(laser.size()))
+                .insert(Collider::new(
+                    Layers::PLAYER_LASER,
+                    Layers::ENEMY | Layers::PLAYER,
+                ))
+                .insert(HitShape::vertical_capsule(laser.size().into()))
+                .insert(Movable { auto_despawn: true })
+                .insert(PreviousPosition(translation))
+                .insert(Velocity { x: 0.0, y: 1.0 });
+
+            laser_fired_events.send(LaserFired {
+                shooter: Shooter::Player,
+                position: translation,
+            });
+        };
+        spawn_laser(x_offset);
+        spawn_laser(-x_offset);
+    }
 }
 
-fn player_keyboard_event_system(
-    kb: Res<Input<KeyCode>>,
-    mut query: Query<&mut Velocity, With<Player>>,
+fn player_control_system(
+    actions: Res<PlayerActions>,
+    config: Res<GameConfig>,
+    time_scale: Res<TimeScale>,
+    mut query: Query<(&Player, &mut Velocity)>,
 ) {
-    if let Ok(mut velocity) = query.get_single_mut() {
-        velocity.x = if kb.pressed(KeyCode::Left) {
-            -1.
-        } else if kb.pressed(KeyCode::Right) {
-            1.
+    for (player, mut velocity) in query.iter_mut() {
+        let movement = actions.0[player.0].movement();
+        let target_x = movement.x;
+        let target_y = if config.player_vertical {
+            movement.y
         } else {
             0.
+        };
+
+        let dt = TIME_STEP * time_scale.0;
+        velocity.x = accelerate(velocity.x, target_x, dt);
+        velocity.y = accelerate(velocity.y, target_y, dt);
+    }
+}
+
+/// Move `current` towards `target`, speeding up with `PLAYER_ACCELERATION`
+/// and slowing down (or turning around) with `PLAYER_DECELERATION`.
+fn accelerate(current: f32, target: f32, dt: f32) -> f32 {
+    let speeding_up = target != 0. && current * target >= 0. && current.abs() < target.abs();
+    let rate = if speeding_up {
+        PLAYER_ACCELERATION
+    } else {
+        PLAYER_DECELERATION
+    };
+    let step = rate * dt;
+
+    if (target - current).abs() <= step {
+        target
+    } else {
+        current + step * (target - current).signum()
+    }
+}
+
+/// Lowest and highest y for the center of a ship of `size`: resting on the
+/// bottom edge, or anywhere in the bottom third in vertical mode.
+fn player_y_range(win_size: &WinSize, size: (f32, f32), vertical: bool) -> (f32, f32) {
+    let half_height = size.1 / 2. * SPRITE_SCALE;
+    let bottom = -win_size.h / 2. + half_height + 5.;
+    let top = if vertical {
+        -win_size.h / 2. + win_size.h / 3. - half_height
+    } else {
+        bottom
+    };
+    (bottom, top.max(bottom))
+}
+
+fn player_bounds_system(
+    win_size: Res<WinSize>,
+    config: Res<GameConfig>,
+    mut query: Query<(&mut Transform, &mut Velocity, &SpriteSize), With<Player>>,
+) {
+    for (mut transform, mut velocity, size) in query.iter_mut() {
+        let half_width = size.0.x / 2. * SPRITE_SCALE;
+        let x_max = (win_size.w / 2. - half_width).max(0.);
+        let (y_min, y_max) = player_y_range(&win_size, size.0.into(), config.player_vertical);
+
+        let translation = &mut transform.translation;
+        let x = translation.x.clamp(-x_max, x_max);
+        let y = translation.y.clamp(y_min, y_max);
+
+        // stop against the edge instead of pushing into it
+        if x != translation.x {
+            velocity.x = 0.;
+        }
+        if y != translation.y {
+            velocity.y = 0.;
         }
+        (translation.x, translation.y) = (x, y);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accelerates_then_decelerates() {
+        let dt = 1. / 60.;
+
+        let mut velocity = 0.;
+        let mut frames = 0;
+        while velocity < 1. {
+            velocity = accelerate(velocity, 1., dt);
+            frames += 1;
+        }
+        assert_eq!(velocity, 1.);
+        assert!(frames > 1, "should ramp up over several frames");
+
+        let mut stop_frames = 0;
+        while velocity > 0. {
+            velocity = accelerate(velocity, 0., dt);
+            stop_frames += 1;
+        }
+        assert_eq!(velocity, 0.);
+        assert!(stop_frames < frames, "braking is quicker than speeding up");
+    }
+
+    #[test]
+    fn vertical_range_covers_the_bottom_third() {
+        let win_size = WinSize::new(800., 720.);
+
+        let (bottom, top) = player_y_range(&win_size, (99., 75.), false);
+        assert_eq!(bottom, top);
+
+        let (vertical_bottom, top) = player_y_range(&win_size, (99., 75.), true);
+        assert_eq!(vertical_bottom, bottom);
+        assert!(top > bottom && top < -720. / 2. + 720. / 3.);
     }
 }
@@ -0,0 +1,347 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub const HIGH_SCORES_FILE: &str = "highscores.ron";
+pub const HIGH_SCORES_MAX: usize = 10;
+pub const INITIALS_LEN: usize = 3;
+
+/// Bump when a change to `HighScores` can't be read by the old code, and
+/// teach `HighScores::migrate` to read the old version.
+const HIGH_SCORES_VERSION: u32 = 2;
+
+/// Resource - where the high scores live, `None` keeps them in memory only
+pub struct HighScoresFile(pub Option<PathBuf>);
+
+impl Default for HighScoresFile {
+    /// In the platform data directory, e.g. `~/.local/share/space-invaders`.
+    fn default() -> Self {
+        Self(dirs::data_dir().map(|dir| dir.join("space-invaders").join(HIGH_SCORES_FILE)))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScoreEntry {
+    pub initials: String,
+    pub score: u32,
+    pub wave: u32,
+    /// `GameRng` seed of the run
+    pub seed: u64,
+    /// `YYYY-MM-DD`, UTC
+    pub date: String,
+    /// 1, or 2 for a co-op game
+    pub players: u32,
+}
+
+/// Resource - the best `HIGH_SCORES_MAX` scores, best first
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct HighScores {
+    pub version: u32,
+    pub entries: Vec<ScoreEntry>,
+}
+
+/// Just the version of a high scores file, to know how to read the rest
+#[derive(Deserialize)]
+struct Versioned {
+    #[serde(default)]
+    version: u32,
+}
+
+/// Version 1 of the file, from before entries knew how many played
+#[derive(Deserialize)]
+struct HighScoresV1 {
+    entries: Vec<ScoreEntryV1>,
+}
+
+#[derive(Deserialize)]
+struct ScoreEntryV1 {
+    initials: String,
+    score: u32,
+    wave: u32,
+    seed: u64,
+    date: String,
+}
+
+impl From<ScoreEntryV1> for ScoreEntry {
+    fn from(entry: ScoreEntryV1) -> Self {
+        Self {
+            initials: entry.initials,
+            score: entry.score,
+            wave: entry.wave,
+            seed: entry.seed,
+            date: entry.date,
+            players: 1,
+        }
+    }
+}
+
+impl From<HighScoresV1> for HighScores {
+    fn from(scores: HighScoresV1) -> Self {
+        Self {
+            version: HIGH_SCORES_VERSION,
+            entries: scores.entries.into_iter().map(ScoreEntry::from).collect(),
+        }
+    }
+}
+
+impl HighScores {
+    /// Would `score` make it into the table?
+    pub fn qualifies(&self, score: u32) -> bool {
+        score > 0
+            && (self.entries.len() < HIGH_SCORES_MAX
+                || self.entries.last().is_some_and(|last| score > last.score))
+    }
+
+    /// Add `entry` in order, dropping whatever falls off the bottom. Returns
+    /// its place, `None` if it didn't make it.
+    pub fn insert(&mut self, entry: ScoreEntry) -> Option<usize> {
+        if !self.qualifies(entry.score) {
+            return None;
+        }
+        // below equal scores, the earlier one keeps its place
+        let place = self
+            .entries
+            .iter()
+            .position(|other| entry.score > other.score)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(place, entry);
+        self.entries.truncate(HIGH_SCORES_MAX);
+        Some(place)
+    }
+
+    pub fn from_ron(text: &str) -> Result<Self, String> {
+        let Versioned { version } =
+            ron::from_str(text).map_err(|err| format!("bad high scores: {err}"))?;
+        Self::migrate(version, text)
+    }
+
+    /// Read a file of any known `version` as the current format.
+    fn migrate(version: u32, text: &str) -> Result<Self, String> {
+        let bad = |err: ron::Error| format!("bad high scores: {err}");
+        let mut scores: Self = match version {
+            HIGH_SCORES_VERSION => ron::from_str(text).map_err(bad)?,
+            1 => ron::from_str::<HighScoresV1>(text).map_err(bad)?.into(),
+            version if version > HIGH_SCORES_VERSION => {
+                return Err(format!(
+                    "high scores version {version} is from a newer version of the game"
+                ))
+            }
+            version => return Err(format!("unknown high scores version {version}")),
+        };
+        scores.sanitize();
+        Ok(scores)
+    }
+
+    /// Hand edits can't break the ordering or the size of the table.
+    fn sanitize(&mut self) {
+        self.entries.sort_by_key(|entry| std::cmp::Reverse(entry.score));
+        self.entries.truncate(HIGH_SCORES_MAX);
+        for entry in self.entries.iter_mut() {
+            entry.initials = entry.initials.chars().take(INITIALS_LEN).collect();
+        }
+    }
+
+    pub fn to_ron(&self) -> Result<String, String> {
+        let scores = Self {
+            version: HIGH_SCORES_VERSION,
+            entries: self.entries.clone(),
+        };
+        ron::ser::to_string_pretty(&scores, ron::ser::PrettyConfig::default())
+            .map_err(|err| format!("can't write high scores: {err}"))
+    }
+
+    /// Empty when there is no file yet.
+    pub fn load(file: &HighScoresFile) -> Result<Self, String> {
+        let path = match &file.0 {
+            Some(path) if path.exists() => path,
+            _ => return Ok(Self::default()),
+        };
+        let text = fs::read_to_string(path)
+            .map_err(|err| format!("can't read {}: {err}", path.display()))?;
+        Self::from_ron(&text)
+    }
+
+    /// Like `load`, but a broken file is moved aside, so it is neither lost
+    /// nor overwritten, and the table starts empty.
+    pub fn load_or_default(file: &HighScoresFile) -> Self {
+        Self::load(file).unwrap_or_else(|err| {
+            warn!("{err}, starting a new high score table");
+            if let Some(path) = &file.0 {
+                let backup = backup_path(path);
+                if let Err(err) = fs::rename(path, &backup) {
+                    warn!("can't move it to {}: {err}", backup.display());
+                }
+            }
+            Self::default()
+        })
+    }
+
+    pub fn save(&self, file: &HighScoresFile) -> Result<(), String> {
+        let path = match &file.0 {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|err| format!("can't create {}: {err}", dir.display()))?;
+        }
+        fs::write(path, self.to_ron()?)
+            .map_err(|err| format!("can't write {}: {err}", path.display()))
+    }
+}
+
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    path.with_file_name(name)
+}
+
+/// Today's date as `YYYY-MM-DD`, UTC.
+pub fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    date_from_days((secs / 86_400) as i64)
+}
+
+/// Civil date of a day count since 1970-01-01 (Howard Hinnant's algorithm).
+fn date_from_days(days: i64) -> String {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(initials: &str, score: u32) -> ScoreEntry {
+        ScoreEntry {
+            initials: initials.to_string(),
+            score,
+            wave: 3,
+            seed: 42,
+            date: "2024-02-29".to_string(),
+            players: 1,
+        }
+    }
+
+    #[test]
+    fn keeps_the_best_ten_in_order() {
+        let mut scores = HighScores::default();
+        for score in 1..=12 {
+            scores.insert(entry("AAA", score * 100));
+        }
+
+        assert_eq!(scores.entries.len(), HIGH_SCORES_MAX);
+        assert_eq!(scores.entries[0].score, 1200);
+        assert_eq!(scores.entries[9].score, 300);
+        assert!(!scores.qualifies(300));
+        assert_eq!(scores.insert(entry("BBB", 1200)), Some(1));
+        assert_eq!(scores.insert(entry("CCC", 0)), None);
+    }
+
+    #[test]
+    fn round_trips_through_ron() {
+        let mut scores = HighScores::default();
+        scores.insert(entry("ZED", 900));
+
+        let text = scores.to_ron().unwrap();
+        let loaded = HighScores::from_ron(&text).unwrap();
+        assert_eq!(loaded.entries, scores.entries);
+        assert_eq!(loaded.version, HIGH_SCORES_VERSION);
+    }
+
+    #[test]
+    fn rejects_corrupt_and_newer_files() {
+        assert!(HighScores::from_ron("(entries: [(initials: ").is_err());
+        assert!(HighScores::from_ron("(version: 99, entries: [])").is_err());
+        assert!(HighScores::from_ron("(entries: [])").is_err());
+    }
+
+    #[test]
+    fn hand_edited_tables_are_put_back_in_order() {
+        let text = r#"(version: 1, entries: [
+            (initials: "LOW", score: 100, wave: 1, seed: 1, date: "2024-01-01"),
+            (initials: "HIGHER", score: 500, wave: 2, seed: 2, date: "2024-01-02"),
+        ])"#;
+
+        let scores = HighScores::from_ron(text).unwrap();
+        assert_eq!(scores.entries[0].initials, "HIG");
+        assert_eq!(scores.entries[1].initials, "LOW");
+    }
+
+    #[test]
+    fn version_1_files_keep_their_scores() {
+        let dir = std::env::temp_dir().join(format!("space-invaders-v1-{}", std::process::id()));
+        let file = HighScoresFile(Some(dir.join(HIGH_SCORES_FILE)));
+        fs::create_dir_all(&dir).unwrap();
+        let v1 = r#"(
+    version: 1,
+    entries: [
+        (initials: "ACE", score: 2400, wave: 6, seed: 7, date: "2024-03-01"),
+        (initials: "BOB", score: 900, wave: 3, seed: 8, date: "2024-03-02"),
+    ],
+)"#;
+        fs::write(file.0.as_ref().unwrap(), v1).unwrap();
+
+        let scores = HighScores::load_or_default(&file);
+        assert!(!dir.join("highscores.ron.bak").exists());
+        assert_eq!(scores.version, HIGH_SCORES_VERSION);
+        assert_eq!(
+            scores.entries,
+            vec![
+                ScoreEntry {
+                    seed: 7,
+                    date: "2024-03-01".to_string(),
+                    wave: 6,
+                    ..entry("ACE", 2400)
+                },
+                ScoreEntry {
+                    seed: 8,
+                    date: "2024-03-02".to_string(),
+                    ..entry("BOB", 900)
+                },
+            ]
+        );
+
+        // saved back in the current format
+        scores.save(&file).unwrap();
+        assert_eq!(HighScores::load(&file).unwrap(), scores);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn broken_files_are_moved_aside() {
+        let dir = std::env::temp_dir().join(format!("space-invaders-{}", std::process::id()));
+        let file = HighScoresFile(Some(dir.join(HIGH_SCORES_FILE)));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(file.0.as_ref().unwrap(), "not ron").unwrap();
+
+        assert_eq!(HighScores::load_or_default(&file), HighScores::default());
+        assert_eq!(
+            fs::read_to_string(dir.join("highscores.ron.bak")).unwrap(),
+            "not ron"
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn dates_from_day_counts() {
+        assert_eq!(date_from_days(0), "1970-01-01");
+        assert_eq!(date_from_days(19_782), "2024-02-29");
+        assert_eq!(date_from_days(-1), "1969-12-31");
+    }
+}
@@ -0,0 +1,326 @@
+use bevy::{prelude::*, window::ReceivedCharacter};
+
+use crate::{
+    components::{Enemy, Explosion, ExplosionToSpawn, Laser, Player},
+    controls::{menu::SettingsMenu, Action, PlayerActions},
+    enemy::formation::FormationMaker,
+    netcode::Online,
+    scores::{today, HighScores, HighScoresFile, ScoreEntry, INITIALS_LEN},
+    EnemyCount, GameConfig, GameRng, Paused, Players, Screen, TimeScale, UiFont, Wave, WaveSpawned,
+    MAX_PLAYERS,
+};
+
+/// Resource - the game over screen, asking each player with a new high score
+/// for their initials in turn
+#[derive(Default)]
+pub struct GameOver {
+    /// players still to enter their initials, best score first
+    pub pending: Vec<usize>,
+    pub initials: String,
+    /// places in the table of this game's new entries
+    pub new_places: Vec<usize>,
+}
+
+/// Component - title or game over screen background node
+#[derive(Component)]
+struct ScreenRoot;
+
+/// Component - title or game over screen text
+#[derive(Component)]
+struct ScreenText;
+
+/// Plugin - title and game over screens, with the high score table
+pub struct ScreensPlugin;
+
+impl Plugin for ScreensPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HighScoresFile>();
+        let high_scores = HighScores::load_or_default(app.world.resource::<HighScoresFile>());
+
+        app.insert_resource(high_scores)
+            .init_resource::<Screen>()
+            .init_resource::<GameOver>()
+            .init_resource::<UiFont>()
+            .init_resource::<Players>()
+            .init_resource::<GameConfig>()
+            .init_resource::<Wave>()
+            .init_resource::<GameRng>()
+            .init_resource::<Paused>()
+            .init_resource::<TimeScale>()
+            .add_event::<ReceivedCharacter>()
+            .add_system(game_over_system)
+            .add_system(screen_pause_system.after(game_over_system))
+            .add_system(screen_ui_system)
+            // after gameplay, so the press that starts a game is not also a shot
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                start_game_system.before(initials_entry_system),
+            )
+            .add_system_to_stage(CoreStage::PostUpdate, initials_entry_system);
+    }
+}
+
+fn game_over_system(
+    mut screen: ResMut<Screen>,
+    mut game_over: ResMut<GameOver>,
+    players: Res<Players>,
+    config: Res<GameConfig>,
+    high_scores: Res<HighScores>,
+) {
+    if *screen != Screen::Playing {
+        return;
+    }
+
+    let in_game = &players.0[..config.players.clamp(1, MAX_PLAYERS)];
+    if in_game
+        .iter()
+        .any(|player| player.alive || player.lives > 0)
+    {
+        return;
+    }
+
+    let mut pending: Vec<_> = (0..in_game.len())
+        .filter(|number| high_scores.qualifies(in_game[*number].score))
+        .collect();
+    pending.sort_by_key(|number| std::cmp::Reverse(in_game[*number].score));
+    *game_over = GameOver {
+        pending,
+        ..Default::default()
+    };
+    *screen = Screen::GameOver;
+}
+
+/// Enter, or any player's fire, starts a fresh game from the title or once
+/// every initial is in.
+fn start_game_system(
+    mut commands: Commands,
+    kb: Res<Input<KeyCode>>,
+    actions: Res<PlayerActions>,
+    settings_menu: Res<SettingsMenu>,
+    game_over: Res<GameOver>,
+    mut screen: ResMut<Screen>,
+    mut paused: ResMut<Paused>,
+    mut time_scale: ResMut<TimeScale>,
+    mut players: ResMut<Players>,
+    mut enemy_count: ResMut<EnemyCount>,
+    mut wave: ResMut<Wave>,
+    mut wave_spawned: ResMut<WaveSpawned>,
+    mut formation_maker: ResMut<FormationMaker>,
+    mut rng: ResMut<GameRng>,
+    online: Option<Res<Online>>,
+    query: Query<
+        Entity,
+        Or<(
+            With<Enemy>,
+            With<Laser>,
+            With<Player>,
+            With<Explosion>,
+            With<ExplosionToSpawn>,
+        )>,
+    >,
+) {
+    let waiting = match *screen {
+        Screen::Title => true,
+        Screen::GameOver => game_over.pending.is_empty(),
+        Screen::Loading | Screen::Playing => false,
+    };
+    let start = kb.just_pressed(KeyCode::Return)
+        || actions
+            .0
+            .iter()
+            .any(|actions| actions.just_pressed(Action::Fire));
+    // an online game is played once, a new one would need both peers
+    if !waiting || !start || settings_menu.open || online.is_some() {
+        return;
+    }
+
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    *players = Players::default();
+    enemy_count.0 = 0;
+    *wave = Wave::default();
+    *wave_spawned = WaveSpawned::default();
+    *formation_maker = FormationMaker::default();
+    *rng = GameRng::default();
+
+    *screen = Screen::Playing;
+    if paused.paused {
+        paused.toggle(&mut time_scale);
+    }
+}
+
+fn initials_entry_system(
+    kb: Res<Input<KeyCode>>,
+    mut received_characters: EventReader<ReceivedCharacter>,
+    mut game_over: ResMut<GameOver>,
+    mut high_scores: ResMut<HighScores>,
+    high_scores_file: Res<HighScoresFile>,
+    players: Res<Players>,
+    wave: Res<Wave>,
+    rng: Res<GameRng>,
+    config: Res<GameConfig>,
+) {
+    let typed: String = received_characters.iter().map(|event| event.char).collect();
+    let number = match game_over.pending.first() {
+        Some(number) => *number,
+        None => return,
+    };
+
+    game_over.initials.extend(
+        typed
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .map(|c| c.to_ascii_uppercase()),
+    );
+    game_over.initials.truncate(INITIALS_LEN);
+    if kb.just_pressed(KeyCode::Back) {
+        game_over.initials.pop();
+    }
+    if !kb.just_pressed(KeyCode::Return) || game_over.initials.is_empty() {
+        return;
+    }
+
+    let entry = ScoreEntry {
+        initials: std::mem::take(&mut game_over.initials),
+        score: players.0[number].score,
+        wave: wave.0,
+        seed: rng.seed,
+        date: today(),
+        players: config.players as u32,
+    };
+    if let Some(place) = high_scores.insert(entry) {
+        for other in game_over
+            .new_places
+            .iter_mut()
+            .filter(|other| **other >= place)
+        {
+            *other += 1;
+        }
+        game_over.new_places.push(place);
+    }
+    if let Err(err) = high_scores.save(&high_scores_file) {
+        warn!("{err}");
+    }
+    game_over.pending.remove(0);
+}
+
+/// Keep the game frozen behind the title and game over screens.
+fn screen_pause_system(
+    screen: Res<Screen>,
+    mut paused: ResMut<Paused>,
+    mut time_scale: ResMut<TimeScale>,
+) {
+    if *screen != Screen::Playing && !paused.paused {
+        paused.toggle(&mut time_scale);
+    }
+}
+
+fn screen_ui_system(
+    mut commands: Commands,
+    screen: Res<Screen>,
+    game_over: Res<GameOver>,
+    high_scores: Res<HighScores>,
+    players: Res<Players>,
+    config: Res<GameConfig>,
+    font: Res<UiFont>,
+    root_query: Query<Entity, With<ScreenRoot>>,
+    mut text_query: Query<&mut Text, With<ScreenText>>,
+) {
+    let open = matches!(*screen, Screen::Title | Screen::GameOver);
+    match (open, root_query.get_single()) {
+        (true, Err(_)) => {
+            commands
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                        position_type: PositionType::Absolute,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    color: Color::rgba(0., 0., 0., 0.85).into(),
+                    ..Default::default()
+                })
+                .insert(ScreenRoot)
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: font.0.clone(),
+                                font_size: 20.,
+                                color: Color::WHITE,
+                            },
+                        ))
+                        .insert(ScreenText);
+                });
+        }
+        (false, Ok(root)) => commands.entity(root).despawn_recursive(),
+        _ => {}
+    }
+
+    if !open {
+        return;
+    }
+
+    let mut lines = Vec::new();
+    match *screen {
+        Screen::Title => {
+            lines.push("SPACE INVADERS".to_string());
+            lines.push(String::new());
+            lines.extend(table_lines(&high_scores, &[]));
+            lines.push(String::new());
+            lines.push("Enter or Fire to start, F1 controls".to_string());
+        }
+        Screen::GameOver => {
+            lines.push("GAME OVER".to_string());
+            lines.push(String::new());
+            for (number, player) in players.0.iter().enumerate().take(config.players) {
+                lines.push(format!("Player {}  {:>7}", number + 1, player.score));
+            }
+            lines.push(String::new());
+            match game_over.pending.first() {
+                Some(number) => {
+                    lines.push(format!("PLAYER {} - NEW HIGH SCORE!", number + 1));
+                    lines.push(format!(
+                        "Enter your initials: {:_<width$}",
+                        game_over.initials,
+                        width = INITIALS_LEN
+                    ));
+                }
+                None => {
+                    lines.extend(table_lines(&high_scores, &game_over.new_places));
+                    lines.push(String::new());
+                    lines.push("Enter or Fire to play again".to_string());
+                }
+            }
+        }
+        Screen::Loading | Screen::Playing => {}
+    }
+
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = lines.join("\n");
+    }
+}
+
+/// The table, marking the places in `new` as just set.
+fn table_lines(high_scores: &HighScores, new: &[usize]) -> Vec<String> {
+    let mut lines = vec!["HIGH SCORES".to_string()];
+    if high_scores.entries.is_empty() {
+        lines.push("no scores yet".to_string());
+    }
+    for (place, entry) in high_scores.entries.iter().enumerate() {
+        let marker = if new.contains(&place) { ">" } else { " " };
+        let co_op = if entry.players > 1 { " co-op" } else { "" };
+        lines.push(format!(
+            "{marker}{:>2}. {:<3} {:>7}  wave {:<3} {}{co_op}",
+            place + 1,
+            entry.initials,
+            entry.score,
+            entry.wave,
+            entry.date
+        ));
+    }
+    lines
+}
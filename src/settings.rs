@@ -0,0 +1,156 @@
+use std::{fs, path::PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    camera::EffectSettings,
+    controls::{Action, Binding, InputBindings},
+    display::DisplaySettings,
+    player::ShipColour,
+    sound::AudioSettings,
+    MAX_PLAYERS,
+};
+
+pub const SETTINGS_FILE: &str = "settings.ron";
+
+/// Resource - where the user settings live, `None` keeps them in memory only
+pub struct SettingsFile(pub Option<PathBuf>);
+
+impl Default for SettingsFile {
+    /// In the platform config directory, e.g. `~/.config/space-invaders`.
+    fn default() -> Self {
+        Self(dirs::config_dir().map(|dir| dir.join("space-invaders").join(SETTINGS_FILE)))
+    }
+}
+
+/// Resource - user settings, loaded from and saved to the `SettingsFile`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// control scheme of each player
+    pub bindings: [InputBindings; MAX_PLAYERS],
+    pub ship_colours: [ShipColour; MAX_PLAYERS],
+    pub audio: AudioSettings,
+    pub display: DisplaySettings,
+    /// accessibility switches for the camera effects
+    pub effects: EffectSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            bindings: std::array::from_fn(InputBindings::for_player),
+            ship_colours: [ShipColour::Blue, ShipColour::Red],
+            audio: AudioSettings::default(),
+            display: DisplaySettings::default(),
+            effects: EffectSettings::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Rebind an action of player `number`. Keys are shared by both players,
+    /// so a key is also taken away from the other player.
+    pub fn rebind(&mut self, number: usize, action: Action, binding: Binding) {
+        if let Binding::Key(_) = binding {
+            for bindings in self.bindings.iter_mut() {
+                bindings.unbind(binding);
+            }
+        }
+        self.bindings[number].rebind(action, binding);
+    }
+
+    pub fn from_ron(text: &str) -> Result<Self, String> {
+        ron::from_str(text).map_err(|err| format!("bad settings: {err}"))
+    }
+
+    pub fn to_ron(&self) -> Result<String, String> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|err| format!("can't write settings: {err}"))
+    }
+
+    /// Defaults when there is no file yet.
+    pub fn load(file: &SettingsFile) -> Result<Self, String> {
+        let path = match &file.0 {
+            Some(path) if path.exists() => path,
+            _ => return Ok(Self::default()),
+        };
+        let text = fs::read_to_string(path)
+            .map_err(|err| format!("can't read {}: {err}", path.display()))?;
+        Self::from_ron(&text)
+    }
+
+    /// Like `load`, falling back to the defaults on a broken file.
+    pub fn load_or_default(file: &SettingsFile) -> Self {
+        Self::load(file).unwrap_or_else(|err| {
+            warn!("{err}, using the default settings");
+            Self::default()
+        })
+    }
+
+    pub fn save(&self, file: &SettingsFile) -> Result<(), String> {
+        let path = match &file.0 {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|err| format!("can't create {}: {err}", dir.display()))?;
+        }
+        fs::write(path, self.to_ron()?)
+            .map_err(|err| format!("can't write {}: {err}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_ron() {
+        let mut settings = Settings::default();
+        settings.rebind(1, Action::Fire, Binding::Key(KeyCode::LControl));
+        settings.ship_colours[0] = ShipColour::Green;
+        settings.audio.muted = true;
+        settings.display.fullscreen = true;
+        settings.effects.screen_shake = false;
+
+        let text = settings.to_ron().unwrap();
+        assert_eq!(Settings::from_ron(&text), Ok(settings));
+    }
+
+    #[test]
+    fn keys_are_taken_from_the_other_player() {
+        let mut settings = Settings::default();
+        settings.rebind(1, Action::Fire, Binding::Key(KeyCode::Space));
+
+        assert!(!settings.bindings[0]
+            .bindings(Action::Fire)
+            .contains(&Binding::Key(KeyCode::Space)));
+        assert_eq!(
+            settings.bindings[1].bindings(Action::Fire)[0],
+            Binding::Key(KeyCode::Space)
+        );
+    }
+
+    #[test]
+    fn missing_fields_fall_back_to_defaults() {
+        assert_eq!(Settings::from_ron("()"), Ok(Settings::default()));
+        assert!(Settings::from_ron("(bindings: 3)").is_err());
+    }
+
+    #[test]
+    fn saving_creates_the_config_directory() {
+        let dir =
+            std::env::temp_dir().join(format!("space-invaders-settings-{}", std::process::id()));
+        let file = SettingsFile(Some(dir.join("nested").join(SETTINGS_FILE)));
+        let mut settings = Settings::default();
+        settings.audio.sfx = 0.2;
+
+        settings.save(&file).unwrap();
+        let loaded = Settings::load(&file);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(loaded, Ok(settings));
+    }
+}
@@ -0,0 +1,402 @@
+use std::collections::{HashMap, VecDeque};
+
+use bevy::{
+    audio::{AudioSink, AudioSource},
+    input::InputSystem,
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    console::ConsoleState,
+    controls::menu::SettingsMenu,
+    events::{
+        EnemyDestroyed, LaserFired, PlayerHit, PowerUpCollected, PowerUpKind, ShieldDown, Shooter,
+    },
+    loading::Loading,
+    netcode::Online,
+    screens::GameOver,
+    settings::{Settings, SettingsFile},
+    Screen,
+};
+
+const MUTE_KEY: KeyCode = KeyCode::M;
+/// copies of one sound effect that can play at once, more are dropped
+pub const SFX_MAX_VOICES: usize = 4;
+/// how long a copy counts as playing before its length is known
+const SFX_DEFAULT_LENGTH: f64 = 0.5;
+
+/// Sound effects, the `assets/Bonus` sfx
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Sfx {
+    PlayerLaser,
+    EnemyLaser,
+    EnemyDestroyed,
+    PlayerDeath,
+    ShieldUp,
+    ShieldDown,
+    PowerUp,
+}
+
+impl Sfx {
+    pub const ALL: [Sfx; 7] = [
+        Sfx::PlayerLaser,
+        Sfx::EnemyLaser,
+        Sfx::EnemyDestroyed,
+        Sfx::PlayerDeath,
+        Sfx::ShieldUp,
+        Sfx::ShieldDown,
+        Sfx::PowerUp,
+    ];
+
+    pub fn path(&self) -> &'static str {
+        match self {
+            Sfx::PlayerLaser => "Bonus/sfx_laser1.ogg",
+            Sfx::EnemyLaser => "Bonus/sfx_laser2.ogg",
+            Sfx::EnemyDestroyed => "Bonus/sfx_zap.ogg",
+            Sfx::PlayerDeath => "Bonus/sfx_lose.ogg",
+            Sfx::ShieldUp => "Bonus/sfx_shieldUp.ogg",
+            Sfx::ShieldDown => "Bonus/sfx_shieldDown.ogg",
+            Sfx::PowerUp => "Bonus/sfx_twoTone.ogg",
+        }
+    }
+}
+
+/// Event - play a sound effect, at the `AudioSettings` volume
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PlaySfx(pub Sfx);
+
+/// Volumes from 0 to 1, part of the user `Settings`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSettings {
+    pub master: f32,
+    pub sfx: f32,
+    pub music: f32,
+    /// toggled with M
+    pub muted: bool,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            master: 1.,
+            sfx: 0.8,
+            music: 0.5,
+            muted: false,
+        }
+    }
+}
+
+impl AudioSettings {
+    pub fn sfx_volume(&self) -> f32 {
+        if self.muted {
+            0.
+        } else {
+            self.master * self.sfx
+        }
+    }
+
+    pub fn music_volume(&self) -> f32 {
+        if self.muted {
+            0.
+        } else {
+            self.master * self.music
+        }
+    }
+}
+
+/// Resource - background track, looped at the music volume
+///
+/// The game's assets have no music yet, so it starts out empty.
+#[derive(Default)]
+pub struct Music(pub Option<Handle<AudioSource>>);
+
+/// Resource - sound effect handles and the copies of each still playing
+pub struct SoundPlayer {
+    handles: HashMap<Sfx, Handle<AudioSource>>,
+    /// seconds, from the Ogg headers once loaded
+    lengths: HashMap<Sfx, f64>,
+    /// start times of the copies of each sound that may still be playing
+    voices: HashMap<Sfx, VecDeque<f64>>,
+}
+
+impl FromWorld for SoundPlayer {
+    fn from_world(world: &mut World) -> Self {
+        // headless apps have no asset server
+        let handles = Sfx::ALL
+            .into_iter()
+            .map(|sfx| {
+                let handle = world
+                    .get_resource::<AssetServer>()
+                    .map_or_else(Handle::default, |asset_server| {
+                        asset_server.load(sfx.path())
+                    });
+                (sfx, handle)
+            })
+            .collect();
+        Self {
+            handles,
+            lengths: HashMap::new(),
+            voices: HashMap::new(),
+        }
+    }
+}
+
+impl SoundPlayer {
+    /// Count a new copy of `sfx` started at `now`, unless `SFX_MAX_VOICES`
+    /// copies are still playing.
+    pub fn start_voice(&mut self, sfx: Sfx, now: f64) -> bool {
+        let length = self
+            .lengths
+            .get(&sfx)
+            .copied()
+            .unwrap_or(SFX_DEFAULT_LENGTH);
+        let voices = self.voices.entry(sfx).or_default();
+        while voices.front().is_some_and(|start| start + length <= now) {
+            voices.pop_front();
+        }
+        if voices.len() >= SFX_MAX_VOICES {
+            return false;
+        }
+        voices.push_back(now);
+        true
+    }
+
+    /// Copies of `sfx` counted as playing.
+    pub fn voices(&self, sfx: Sfx) -> usize {
+        self.voices.get(&sfx).map_or(0, VecDeque::len)
+    }
+}
+
+/// Length in seconds of an Ogg Vorbis file: the last page's granule position
+/// (samples per channel) over the sample rate of the identification header.
+pub fn ogg_length(bytes: &[u8]) -> Option<f64> {
+    let find = |pattern: &[u8]| bytes.windows(pattern.len()).position(|w| w == pattern);
+    let rfind = |pattern: &[u8]| bytes.windows(pattern.len()).rposition(|w| w == pattern);
+
+    // packet type, "vorbis", version u32, channels u8, sample rate u32
+    let header = find(b"\x01vorbis")?;
+    let rate = u32::from_le_bytes(bytes.get(header + 12..header + 16)?.try_into().ok()?);
+    // capture pattern, version u8, header type u8, granule position u64
+    let last_page = rfind(b"OggS")?;
+    let samples = u64::from_le_bytes(bytes.get(last_page + 6..last_page + 14)?.try_into().ok()?);
+
+    (rate > 0).then(|| samples as f64 / rate as f64)
+}
+
+/// Plugin - sound effects on gameplay events and background music, with
+/// volumes from the `Settings` and M to mute
+///
+/// Plays through bevy's `AudioPlugin`, without it only the sounds to play are
+/// worked out.
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SettingsFile>()
+            .init_resource::<Settings>()
+            .init_resource::<Audio<AudioSource>>()
+            .init_resource::<Music>()
+            .init_resource::<SoundPlayer>()
+            .add_event::<PlaySfx>()
+            .add_event::<LaserFired>()
+            .add_event::<EnemyDestroyed>()
+            .add_event::<PlayerHit>()
+            .add_event::<PowerUpCollected>()
+            .add_event::<ShieldDown>();
+
+        // wait for the sounds on the loading screen
+        let sounds: Vec<_> = app
+            .world
+            .resource::<SoundPlayer>()
+            .handles
+            .values()
+            .cloned()
+            .collect();
+        if let Some(mut loading) = app.world.get_resource_mut::<Loading>() {
+            for handle in sounds.iter() {
+                loading.add(handle);
+            }
+        }
+
+        // before the menu and console have seen this frame's keys
+        app.add_system_to_stage(CoreStage::PreUpdate, mute_system.after(InputSystem))
+            .add_system_to_stage(CoreStage::PostUpdate, sfx_event_system)
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                play_sfx_system.after(sfx_event_system),
+            )
+            .add_system(sound_length_system)
+            .add_system(music_system);
+    }
+}
+
+/// M toggles mute, unless it is being typed or bound.
+fn mute_system(
+    kb: Res<Input<KeyCode>>,
+    console: Option<Res<ConsoleState>>,
+    menu: Option<Res<SettingsMenu>>,
+    screen: Option<Res<Screen>>,
+    game_over: Option<Res<GameOver>>,
+    settings_file: Res<SettingsFile>,
+    mut settings: ResMut<Settings>,
+) {
+    let typing = console.is_some_and(|console| console.open)
+        || menu.is_some_and(|menu| menu.listening)
+        || matches!(
+            (screen.as_deref(), game_over),
+            (Some(Screen::GameOver), Some(game_over)) if !game_over.pending.is_empty()
+        );
+    if kb.just_pressed(MUTE_KEY) && !typing {
+        settings.audio.muted = !settings.audio.muted;
+        if let Err(err) = settings.save(&settings_file) {
+            warn!("{err}");
+        }
+    }
+}
+
+/// The sound of each gameplay event.
+fn sfx_event_system(
+    online: Option<Res<Online>>,
+    mut laser_fired_events: EventReader<LaserFired>,
+    mut enemy_destroyed_events: EventReader<EnemyDestroyed>,
+    mut player_hit_events: EventReader<PlayerHit>,
+    mut power_up_events: EventReader<PowerUpCollected>,
+    mut shield_down_events: EventReader<ShieldDown>,
+    mut play_sfx_events: EventWriter<PlaySfx>,
+) {
+    // a replayed tick's sounds played the first time round
+    if online.is_some_and(|online| online.resimulating) {
+        laser_fired_events.clear();
+        enemy_destroyed_events.clear();
+        player_hit_events.clear();
+        power_up_events.clear();
+        shield_down_events.clear();
+        return;
+    }
+
+    for event in laser_fired_events.iter() {
+        play_sfx_events.send(PlaySfx(match event.shooter {
+            Shooter::Player => Sfx::PlayerLaser,
+            Shooter::Enemy => Sfx::EnemyLaser,
+        }));
+    }
+    for _ in enemy_destroyed_events.iter() {
+        play_sfx_events.send(PlaySfx(Sfx::EnemyDestroyed));
+    }
+    for _ in player_hit_events.iter() {
+        play_sfx_events.send(PlaySfx(Sfx::PlayerDeath));
+    }
+    for event in power_up_events.iter() {
+        play_sfx_events.send(PlaySfx(match event.kind {
+            PowerUpKind::Shield => Sfx::ShieldUp,
+            PowerUpKind::Bolt | PowerUpKind::Star => Sfx::PowerUp,
+        }));
+    }
+    for _ in shield_down_events.iter() {
+        play_sfx_events.send(PlaySfx(Sfx::ShieldDown));
+    }
+}
+
+fn play_sfx_system(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    audio: Res<Audio<AudioSource>>,
+    mut player: ResMut<SoundPlayer>,
+    mut play_sfx_events: EventReader<PlaySfx>,
+) {
+    let volume = settings.audio.sfx_volume();
+    let now = time.seconds_since_startup();
+
+    for PlaySfx(sfx) in play_sfx_events.iter() {
+        if volume <= 0. || !player.start_voice(*sfx, now) {
+            continue;
+        }
+        audio.play_with_settings(
+            player.handles[sfx].clone(),
+            PlaybackSettings::ONCE.with_volume(volume),
+        );
+    }
+}
+
+/// Read each sound's length once it has loaded, to know when copies end.
+fn sound_length_system(sources: Option<Res<Assets<AudioSource>>>, mut player: ResMut<SoundPlayer>) {
+    let sources = match sources {
+        Some(sources) if player.lengths.len() < Sfx::ALL.len() => sources,
+        _ => return,
+    };
+    for sfx in Sfx::ALL {
+        if player.lengths.contains_key(&sfx) {
+            continue;
+        }
+        if let Some(source) = sources.get(&player.handles[&sfx]) {
+            let length = ogg_length(&source.bytes).unwrap_or(SFX_DEFAULT_LENGTH);
+            player.lengths.insert(sfx, length);
+        }
+    }
+}
+
+/// Start the `Music` and keep it at the music volume.
+fn music_system(
+    audio: Res<Audio<AudioSource>>,
+    music: Res<Music>,
+    settings: Res<Settings>,
+    sinks: Option<Res<Assets<AudioSink>>>,
+    mut sink: Local<Option<Handle<AudioSink>>>,
+) {
+    let (track, sinks) = match (&music.0, sinks) {
+        (Some(track), Some(sinks)) => (track, sinks),
+        _ => return,
+    };
+
+    match &*sink {
+        None => {
+            let weak = audio.play_with_settings(
+                track.clone(),
+                PlaybackSettings::LOOP.with_volume(settings.audio.music_volume()),
+            );
+            *sink = Some(sinks.get_handle(weak));
+        }
+        Some(handle) if settings.is_changed() => {
+            if let Some(sink) = sinks.get(handle) {
+                sink.set_volume(settings.audio.music_volume());
+            }
+        }
+        Some(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copies_of_a_sound_are_capped_until_they_end() {
+        let mut player = SoundPlayer {
+            handles: HashMap::new(),
+            lengths: [(Sfx::PlayerLaser, 0.3)].into_iter().collect(),
+            voices: HashMap::new(),
+        };
+
+        for _ in 0..SFX_MAX_VOICES {
+            assert!(player.start_voice(Sfx::PlayerLaser, 1.));
+        }
+        assert!(!player.start_voice(Sfx::PlayerLaser, 1.2));
+        assert!(player.start_voice(Sfx::EnemyLaser, 1.2));
+
+        assert!(player.start_voice(Sfx::PlayerLaser, 1.3));
+        assert_eq!(player.voices(Sfx::PlayerLaser), 1);
+    }
+
+    #[test]
+    fn reads_ogg_lengths() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("assets")
+            .join(Sfx::PlayerDeath.path());
+        let length = ogg_length(&std::fs::read(path).unwrap()).unwrap();
+
+        assert!(length > 0.1 && length < 5., "{length}");
+        assert_eq!(ogg_length(b"not ogg"), None);
+    }
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use bevy::prelude::*;
+use common::TestApp;
+use space_invaders::{
+    animation::SpriteAnimation,
+    components::{Explosion, ExplosionKind, ExplosionToSpawn, Player},
+    Players,
+};
+
+#[test]
+fn player_ship_has_an_animated_engine_flame() {
+    let mut game = TestApp::new();
+    game.step_until(120, |world| world.resource::<Players>().0[0].alive);
+    let player = game.entities::<With<Player>>()[0];
+
+    let flame = game.world().get::<Children>(player).unwrap()[0];
+    let frames = game.textures().engine_fire.map(|frame| frame.index);
+    let mut shown = Vec::new();
+    for _ in 0..30 {
+        game.tick();
+        shown.push(game.world().get::<TextureAtlasSprite>(flame).unwrap().index);
+    }
+    assert!(frames.iter().all(|frame| shown.contains(frame)));
+    assert!(game.world().get::<SpriteAnimation>(flame).is_some());
+}
+
+#[test]
+fn explosions_play_once_then_despawn() {
+    let mut game = TestApp::new();
+    game.world()
+        .spawn()
+        .insert(ExplosionToSpawn::new(ExplosionKind::Burst, Vec3::ZERO));
+    game.tick();
+    let explosion = game.entities::<With<Explosion>>()[0];
+
+    game.step(20);
+    let index = game
+        .world()
+        .get::<TextureAtlasSprite>(explosion)
+        .unwrap()
+        .index;
+    assert!(index > 0 && index < 15, "{index}");
+
+    // 16 frames at 20 fps
+    game.step(30);
+    assert!(game.world().get_entity(explosion).is_none());
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use bevy::prelude::*;
+use common::TestApp;
+use space_invaders::{
+    background::{tile_grid, wave_palette, BackgroundTile, Star},
+    Wave, WinSize,
+};
+
+fn star_positions(game: &mut TestApp) -> Vec<(Entity, Vec3)> {
+    let mut query = game.world().query::<(Entity, &Transform, &Star)>();
+    query
+        .iter(game.world())
+        .map(|(entity, transform, _)| (entity, transform.translation))
+        .collect()
+}
+
+#[test]
+fn tiles_and_stars_cover_the_window_and_scroll_down() {
+    let mut game = TestApp::new();
+    game.tick();
+    let (w, h) = {
+        let win_size = game.world().resource::<WinSize>();
+        (win_size.w, win_size.h)
+    };
+
+    let (columns, rows) = tile_grid(w, h);
+    assert_eq!(game.count::<With<BackgroundTile>>(), columns * rows);
+    let before = star_positions(&mut game);
+    assert!(before.len() > 50, "{}", before.len());
+    assert!(before
+        .iter()
+        .all(|(_, position)| position.x.abs() <= w / 2. && position.y.abs() <= h / 2.));
+
+    game.step(10);
+    let after = star_positions(&mut game);
+    let moved_down = before
+        .iter()
+        .filter(|(entity, position)| {
+            after
+                .iter()
+                .any(|(other, moved)| other == entity && moved.y < position.y)
+        })
+        .count();
+    // only stars wrapping back to the top moved up
+    assert!(moved_down > before.len() * 9 / 10, "{moved_down}");
+}
+
+#[test]
+fn resized_window_is_laid_out_again() {
+    let mut game = TestApp::new();
+    game.tick();
+
+    game.world().insert_resource(WinSize::new(1920., 1080.));
+    game.tick();
+
+    let (columns, rows) = tile_grid(1920., 1080.);
+    assert_eq!(game.count::<With<BackgroundTile>>(), columns * rows);
+    let stars = star_positions(&mut game);
+    assert!(stars.iter().any(|(_, position)| position.x.abs() > 400.));
+}
+
+#[test]
+fn new_wave_changes_the_star_colours() {
+    let mut game = TestApp::new();
+    game.tick();
+
+    game.world().resource_mut::<Wave>().0 = 2;
+    game.tick();
+
+    let palette = wave_palette(2);
+    let mut query = game.world().query::<(&Star, &Sprite)>();
+    assert!(query.iter(game.world()).all(|(star, sprite)| {
+        let colour = sprite.color;
+        (colour.r(), colour.g(), colour.b())
+            == (
+                palette[star.0].r(),
+                palette[star.0].g(),
+                palette[star.0].b(),
+            )
+    }));
+}
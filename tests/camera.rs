@@ -0,0 +1,119 @@
+mod common;
+
+use bevy::prelude::*;
+use common::TestApp;
+use space_invaders::{
+    camera::{CameraEffects, DamageFlash, HitStop},
+    events::{EnemyDestroyed, PlayerHit},
+    settings::Settings,
+    GameConfig, TimeScale, WaveSpawned, WAVE_SIZE,
+};
+
+fn spawn_camera(game: &mut TestApp) -> Entity {
+    game.world()
+        .spawn()
+        .insert(Transform::default())
+        .insert(CameraEffects::default())
+        .id()
+}
+
+fn hit_player(game: &mut TestApp) {
+    let player = game.world().spawn().id();
+    game.world().send_event(PlayerHit {
+        player,
+        number: 0,
+        position: Vec3::ZERO,
+    });
+}
+
+#[test]
+fn player_hit_shakes_the_camera_and_flashes_red() {
+    let mut game = TestApp::new();
+    let camera = spawn_camera(&mut game);
+    game.tick();
+
+    hit_player(&mut game);
+    game.tick();
+    assert!(game.world().get::<CameraEffects>(camera).unwrap().trauma > 0.5);
+    assert_eq!(game.count::<With<DamageFlash>>(), 1);
+
+    game.tick();
+    let moved = game.world().get::<Transform>(camera).unwrap().translation;
+    assert_ne!(moved.truncate(), Vec2::ZERO);
+
+    // both wear off within a second
+    game.step(60);
+    assert_eq!(
+        game.world().get::<CameraEffects>(camera).unwrap().trauma,
+        0.
+    );
+    assert_eq!(game.count::<With<DamageFlash>>(), 0);
+}
+
+#[test]
+fn last_enemy_freezes_the_game_briefly() {
+    let mut game = TestApp::new();
+    game.world().resource_mut::<GameConfig>().enemy_max = 0;
+    // the whole wave is out, this is its last
+    game.world().resource_mut::<WaveSpawned>().0 = WAVE_SIZE;
+    game.tick();
+
+    let enemy = game.world().spawn().id();
+    game.world().send_event(EnemyDestroyed {
+        enemy,
+        position: Vec3::ZERO,
+        by_player: None,
+    });
+    game.tick();
+    assert!(game.world().resource::<HitStop>().is_active());
+    assert_eq!(game.world().resource::<TimeScale>().0, 0.);
+
+    game.step(10);
+    assert!(!game.world().resource::<HitStop>().is_active());
+    assert_eq!(game.world().resource::<TimeScale>().0, 1.);
+}
+
+#[test]
+fn clearing_a_wave_at_once_shakes_once() {
+    let mut game = TestApp::new();
+    let camera = spawn_camera(&mut game);
+    game.world().resource_mut::<GameConfig>().enemy_max = 0;
+    game.world().resource_mut::<WaveSpawned>().0 = WAVE_SIZE;
+    game.tick();
+
+    for _ in 0..5 {
+        let enemy = game.world().spawn().id();
+        game.world().send_event(EnemyDestroyed {
+            enemy,
+            position: Vec3::ZERO,
+            by_player: None,
+        });
+    }
+    game.tick();
+
+    let trauma = game.world().get::<CameraEffects>(camera).unwrap().trauma;
+    assert!(trauma > 0.5 && trauma < 1., "trauma {trauma}");
+}
+
+#[test]
+fn effects_can_be_switched_off() {
+    let mut game = TestApp::new();
+    let camera = spawn_camera(&mut game);
+    let effects = &mut game.world().resource_mut::<Settings>().effects;
+    effects.screen_shake = false;
+    effects.damage_flash = false;
+    game.tick();
+
+    hit_player(&mut game);
+    game.step(2);
+
+    assert_eq!(
+        game.world().get::<CameraEffects>(camera).unwrap().trauma,
+        0.
+    );
+    assert_eq!(
+        *game.world().get::<Transform>(camera).unwrap(),
+        Transform::default()
+    );
+    assert_eq!(game.count::<With<DamageFlash>>(), 0);
+}
@@ -0,0 +1,170 @@
+#![allow(dead_code)]
+
+use std::{fs, path::Path, time::Duration};
+
+use bevy::{
+    ecs::query::WorldQuery,
+    input::{
+        gamepad::{
+            gamepad_connection_system, gamepad_event_system, GamepadEventRaw, GamepadEventType,
+        },
+        InputSystem,
+    },
+    prelude::*,
+    time::{create_time_channels, TimeSender},
+    utils::Instant,
+};
+use space_invaders::{
+    animation::AnimationPlugin,
+    atlas::{SheetListing, SpriteSheet},
+    background::BackgroundPlugin,
+    camera::CameraEffectsPlugin,
+    collision::CollisionPlugin,
+    console::ConsolePlugin,
+    controls::ControlsPlugin,
+    debug::DebugPlugin,
+    display::DisplayPlugin,
+    enemy::EnemyPlugin,
+    gamepad::GamepadPlugin,
+    particles::ParticlePlugin,
+    player::PlayerPlugin,
+    scores::HighScoresFile,
+    screens::ScreensPlugin,
+    settings::{Settings, SettingsFile},
+    sound::SoundPlugin,
+    EnemyCount, GameRng, GameTextures, WinSize, SPRITE_SHEET, TIME_STEP, WINDOW_HEIGHT,
+    WINDOW_WIDTH,
+};
+
+const TEST_SEED: u64 = 1;
+
+/// Headless game app: the gameplay plugins on `MinimalPlugins`, with a fake
+/// clock advanced by `TIME_STEP` per tick, keyboard input set by hand and
+/// gamepads driven by raw events, like gilrs would send them.
+pub struct TestApp {
+    pub app: App,
+    time_sender: TimeSender,
+    now: Instant,
+}
+
+/// No asset server headless: the sprites of the real sheet listing, with
+/// default handles, which are enough for the ECS.
+fn game_textures() -> GameTextures {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("assets")
+        .join(SPRITE_SHEET);
+    let listing = SheetListing::parse(&fs::read_to_string(path).unwrap()).unwrap();
+    let sheet = SpriteSheet::new(&listing, Handle::default(), Handle::default());
+    GameTextures::new(&sheet, &Settings::default(), Default::default()).unwrap()
+}
+
+impl TestApp {
+    pub fn new() -> Self {
+        let mut app = App::new();
+        let (time_sender, time_receiver) = create_time_channels();
+
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(time_receiver)
+            .insert_resource(WinSize::new(WINDOW_WIDTH as f32, WINDOW_HEIGHT as f32))
+            .insert_resource(game_textures())
+            .insert_resource(Input::<KeyCode>::default())
+            .add_event::<GamepadEventRaw>()
+            .init_resource::<Axis<GamepadButton>>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                gamepad_event_system.label(InputSystem),
+            )
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                gamepad_connection_system.after(InputSystem),
+            )
+            .insert_resource(EnemyCount(0))
+            // the same enemies and enemy fire on every run
+            .insert_resource(GameRng::new(TEST_SEED))
+            // never touch the developer's settings or high scores
+            .insert_resource(SettingsFile(None))
+            .insert_resource(HighScoresFile(None))
+            .add_plugin(DisplayPlugin)
+            .add_plugin(BackgroundPlugin)
+            .add_plugin(ControlsPlugin)
+            .add_plugin(SoundPlugin)
+            .add_plugin(AnimationPlugin)
+            .add_plugin(ParticlePlugin)
+            .add_plugin(CameraEffectsPlugin)
+            .add_plugin(PlayerPlugin)
+            .add_plugin(GamepadPlugin)
+            .add_plugin(EnemyPlugin)
+            .add_plugin(CollisionPlugin)
+            .add_plugin(ScreensPlugin)
+            .add_plugin(DebugPlugin)
+            .add_plugin(ConsolePlugin);
+
+        let now = app.world.resource::<Time>().startup();
+
+        Self {
+            app,
+            time_sender,
+            now,
+        }
+    }
+
+    pub fn world(&mut self) -> &mut World {
+        &mut self.app.world
+    }
+
+    pub fn press(&mut self, key: KeyCode) {
+        self.app.world.resource_mut::<Input<KeyCode>>().press(key);
+    }
+
+    pub fn release(&mut self, key: KeyCode) {
+        self.app.world.resource_mut::<Input<KeyCode>>().release(key);
+    }
+
+    /// Queue a raw gamepad event, applied at the start of the next tick.
+    pub fn gamepad(&mut self, id: usize, event_type: GamepadEventType) {
+        self.app
+            .world
+            .send_event(GamepadEventRaw::new(Gamepad::new(id), event_type));
+    }
+
+    /// Run one frame, `TIME_STEP` seconds after the previous one.
+    pub fn tick(&mut self) {
+        self.now += Duration::from_secs_f32(TIME_STEP);
+        self.time_sender.0.send(self.now).unwrap();
+        self.app.update();
+
+        // what InputPlugin would do at the start of the next frame
+        self.app.world.resource_mut::<Input<KeyCode>>().clear();
+    }
+
+    pub fn step(&mut self, ticks: usize) {
+        for _ in 0..ticks {
+            self.tick();
+        }
+    }
+
+    /// Step until `done` returns true, panicking after `max_ticks`.
+    pub fn step_until(&mut self, max_ticks: usize, mut done: impl FnMut(&mut World) -> bool) {
+        for _ in 0..max_ticks {
+            if done(&mut self.app.world) {
+                return;
+            }
+            self.tick();
+        }
+        panic!("condition not met after {max_ticks} ticks");
+    }
+
+    /// All entities matching the filter `F`, e.g. `(With<Laser>, With<FromPlayer>)`.
+    pub fn entities<F: WorldQuery>(&mut self) -> Vec<Entity> {
+        let mut query = self.app.world.query_filtered::<Entity, F>();
+        query.iter(&self.app.world).collect()
+    }
+
+    pub fn textures(&mut self) -> &GameTextures {
+        self.app.world.resource::<GameTextures>()
+    }
+
+    pub fn count<F: WorldQuery>(&mut self) -> usize {
+        self.entities::<F>().len()
+    }
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use bevy::{
+    prelude::*,
+    window::{ReceivedCharacter, WindowId},
+};
+use common::TestApp;
+use space_invaders::{
+    collision::{Collider, Layers},
+    components::{Enemy, FromEnemy, Laser, Player, SpriteSize},
+    console::ConsoleState,
+    EnemyCount, Players, TimeScale, SPRITE_SCALE,
+};
+
+fn run(game: &mut TestApp, line: &str) {
+    game.world().resource_mut::<ConsoleState>().submit(line);
+    game.tick();
+}
+
+#[test]
+fn grave_opens_the_console_and_typing_submits() {
+    let mut game = TestApp::new();
+
+    game.press(KeyCode::Grave);
+    game.tick();
+    assert!(game.world().resource::<ConsoleState>().open);
+
+    for char in "god on".chars() {
+        game.world().send_event(ReceivedCharacter {
+            id: WindowId::primary(),
+            char,
+        });
+    }
+    game.press(KeyCode::Return);
+    game.tick();
+    game.tick();
+
+    let console = game.world().resource::<ConsoleState>();
+    assert!(console.input.is_empty());
+    assert!(console.log.iter().any(|line| line == "> god on"));
+    assert!(game.world().resource::<Players>().0[0].god);
+}
+
+#[test]
+fn keys_typed_into_the_console_do_not_reach_the_game() {
+    let mut game = TestApp::new();
+    game.step_until(120, |world| world.resource::<Players>().0[0].alive);
+
+    game.press(KeyCode::Grave);
+    game.tick();
+    game.press(KeyCode::Space);
+    game.tick();
+
+    assert_eq!(game.count::<With<Laser>>(), 0);
+}
+
+#[test]
+fn god_mode_ignores_enemy_lasers() {
+    let mut game = TestApp::new();
+    game.step_until(120, |world| world.resource::<Players>().0[0].alive);
+    let player = game.entities::<With<Player>>()[0];
+    let player_tf = *game.world().get::<Transform>(player).unwrap();
+
+    run(&mut game, "god on");
+    let laser = game.textures().enemy_laser;
+    game.world()
+        .spawn()
+        .insert(Laser)
+        .insert(FromEnemy)
+        .insert(SpriteSize(laser.size()))
+        .insert(Collider::new(Layers::ENEMY_LASER, Layers::PLAYER))
+        .insert(Transform {
+            translation: player_tf.translation,
+            scale: Vec3::new(SPRITE_SCALE, SPRITE_SCALE, 1.),
+            ..Default::default()
+        });
+    game.tick();
+
+    assert!(game.world().resource::<Players>().0[0].alive);
+    assert_eq!(game.count::<With<Player>>(), 1);
+}
+
+#[test]
+fn kill_all_clears_the_enemies() {
+    let mut game = TestApp::new();
+    run(&mut game, "spawn enemy invader 0 100");
+    run(&mut game, "spawn enemy invader 50 100");
+    assert_eq!(game.count::<With<Enemy>>(), 2);
+
+    run(&mut game, "kill_all");
+    game.tick();
+
+    assert_eq!(game.count::<With<Enemy>>(), 0);
+    assert_eq!(game.world().resource::<EnemyCount>().0, 0);
+}
+
+#[test]
+fn timescale_zero_freezes_movement() {
+    let mut game = TestApp::new();
+    run(&mut game, "spawn enemy invader 0 100");
+    run(&mut game, "timescale 0");
+    let enemy = game.entities::<With<Enemy>>()[0];
+    let before = game.world().get::<Transform>(enemy).unwrap().translation;
+
+    game.step(10);
+
+    assert_eq!(game.world().resource::<TimeScale>().0, 0.);
+    assert_eq!(
+        game.world().get::<Transform>(enemy).unwrap().translation,
+        before
+    );
+}
+
+#[test]
+fn bad_input_is_reported_in_the_log() {
+    let mut game = TestApp::new();
+    run(&mut game, "wave lots");
+
+    let console = game.world().resource::<ConsoleState>();
+    assert_eq!(console.log.last().unwrap(), "not a number: lots");
+}
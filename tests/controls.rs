@@ -0,0 +1,130 @@
+mod common;
+
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use common::TestApp;
+use space_invaders::{
+    components::{Laser, Player, Velocity},
+    controls::{menu::SettingsMenu, Action, ActionFrame, Binding, InputSource, InputSources},
+    settings::{Settings, SettingsFile},
+    Paused, Players,
+};
+
+fn spawn_player(game: &mut TestApp) -> Entity {
+    game.step_until(120, |world| world.resource::<Players>().0[0].alive);
+    game.entities::<With<Player>>()[0]
+}
+
+fn tap(game: &mut TestApp, key: KeyCode) {
+    game.press(key);
+    game.tick();
+    game.release(key);
+    game.tick();
+}
+
+#[test]
+fn rebinding_fire_in_the_menu_saves_and_takes_effect() {
+    let path = std::env::temp_dir().join(format!("space-invaders-{}.ron", std::process::id()));
+    let mut game = TestApp::new();
+    game.world()
+        .insert_resource(SettingsFile(Some(path.clone())));
+    spawn_player(&mut game);
+
+    tap(&mut game, KeyCode::F1);
+    assert!(game.world().resource::<SettingsMenu>().open);
+    // Move left, Move right, Move up, Move down, Fire
+    for _ in 0..4 {
+        tap(&mut game, KeyCode::Down);
+    }
+    tap(&mut game, KeyCode::Return);
+    assert!(game.world().resource::<SettingsMenu>().listening);
+    tap(&mut game, KeyCode::F);
+    tap(&mut game, KeyCode::Escape);
+    assert!(!game.world().resource::<SettingsMenu>().open);
+
+    let bindings = game.world().resource::<Settings>().bindings.clone();
+    assert_eq!(bindings[0].bindings(Action::Fire)[0], Binding::Key(KeyCode::F));
+    let saved = Settings::load(&SettingsFile(Some(path.clone()))).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(saved.bindings, bindings);
+
+    tap(&mut game, KeyCode::Space);
+    assert_eq!(game.count::<With<Laser>>(), 0);
+    game.press(KeyCode::F);
+    game.tick();
+    assert_eq!(game.count::<With<Laser>>(), 2);
+}
+
+#[test]
+fn menu_keeps_keys_away_from_the_game() {
+    let mut game = TestApp::new();
+    let player = spawn_player(&mut game);
+
+    tap(&mut game, KeyCode::F1);
+    game.press(KeyCode::Left);
+    game.step(10);
+
+    assert_eq!(game.world().get::<Velocity>(player).unwrap().x, 0.);
+}
+
+#[test]
+fn replayed_frames_drive_the_player() {
+    let mut game = TestApp::new();
+    let player = spawn_player(&mut game);
+
+    let frame = |actions: &[Action]| ActionFrame {
+        held: actions.iter().copied().collect(),
+        stick: Vec2::ZERO,
+    };
+    let mut frames = VecDeque::from(vec![frame(&[Action::MoveRight]); 10]);
+    frames.push_back(frame(&[Action::Fire]));
+    frames.push_back(frame(&[Action::Pause]));
+    game.world().resource_mut::<InputSources>().0[0] = InputSource::Replay(frames);
+
+    game.step(12);
+
+    assert!(game.world().get::<Velocity>(player).unwrap().x > 0.);
+    assert_eq!(game.count::<With<Laser>>(), 2);
+    assert!(game.world().resource::<Paused>().paused);
+}
+
+#[test]
+fn volumes_are_set_below_the_actions() {
+    let mut game = TestApp::new();
+
+    tap(&mut game, KeyCode::F1);
+    // past the actions to Master, then Effects
+    for _ in 0..Action::ALL.len() + 1 {
+        tap(&mut game, KeyCode::Down);
+    }
+    tap(&mut game, KeyCode::Left);
+    tap(&mut game, KeyCode::Left);
+    tap(&mut game, KeyCode::Return);
+
+    let menu = game.world().resource::<SettingsMenu>();
+    assert_eq!(menu.player, 0);
+    assert!(!menu.listening);
+    let audio = &game.world().resource::<Settings>().audio;
+    assert_eq!(audio.sfx, 0.6);
+    assert_eq!(audio.master, 1.);
+}
+
+#[test]
+fn effects_are_switched_below_the_volumes() {
+    let mut game = TestApp::new();
+
+    tap(&mut game, KeyCode::F1);
+    // up from the first action wraps around to the last effect
+    tap(&mut game, KeyCode::Up);
+    tap(&mut game, KeyCode::Return);
+    tap(&mut game, KeyCode::Up);
+    tap(&mut game, KeyCode::Right);
+    tap(&mut game, KeyCode::Right);
+
+    assert!(!game.world().resource::<SettingsMenu>().listening);
+    let effects = &game.world().resource::<Settings>().effects;
+    assert!(!effects.damage_flash);
+    assert!(effects.hit_stop);
+    assert!(effects.screen_shake);
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use bevy::prelude::*;
+use common::TestApp;
+use space_invaders::{
+    collision::{Collider, Layers},
+    components::{Enemy, FromPlayer, Laser, Player, SpriteSize},
+    GameConfig, Players, ENEMY_POINTS, PLAYER_LIVES,
+};
+
+fn spawn_players(game: &mut TestApp) -> [Entity; 2] {
+    game.world().resource_mut::<GameConfig>().players = 2;
+    game.step_until(120, |world| {
+        world
+            .resource::<Players>()
+            .0
+            .iter()
+            .all(|player| player.alive)
+    });
+
+    let mut query = game.world().query::<(Entity, &Player)>();
+    let mut players: Vec<_> = query.iter(game.world()).collect();
+    players.sort_by_key(|(_, player)| player.0);
+    [players[0].0, players[1].0]
+}
+
+fn x(game: &mut TestApp, entity: Entity) -> f32 {
+    game.world().get::<Transform>(entity).unwrap().translation.x
+}
+
+fn shoot(game: &mut TestApp, shooter: usize, target: Entity, mask: Layers) {
+    let target_tf = *game.world().get::<Transform>(target).unwrap();
+    let laser = game.textures().player_laser;
+    game.world()
+        .spawn()
+        .insert(Laser)
+        .insert(FromPlayer(shooter))
+        .insert(SpriteSize(laser.size()))
+        .insert(Collider::new(Layers::PLAYER_LASER, mask))
+        .insert(target_tf);
+    game.tick();
+}
+
+#[test]
+fn players_spawn_side_by_side_on_separate_keys() {
+    let mut game = TestApp::new();
+    let [one, two] = spawn_players(&mut game);
+    let (one_x, two_x) = (x(&mut game, one), x(&mut game, two));
+    assert!(one_x < two_x);
+
+    game.press(KeyCode::A);
+    game.step(10);
+
+    assert_eq!(x(&mut game, one), one_x);
+    assert!(x(&mut game, two) < two_x);
+}
+
+#[test]
+fn kills_score_for_the_shooter() {
+    let mut game = TestApp::new();
+    spawn_players(&mut game);
+    game.step_until(120, |world| {
+        world
+            .query_filtered::<(), With<Enemy>>()
+            .iter(world)
+            .next()
+            .is_some()
+    });
+    let enemy = game.entities::<With<Enemy>>()[0];
+
+    shoot(&mut game, 1, enemy, Layers::ENEMY);
+
+    let players = &game.world().resource::<Players>().0;
+    assert_eq!(players[0].score, 0);
+    assert_eq!(players[1].score, ENEMY_POINTS);
+}
+
+#[test]
+fn friendly_fire_is_off_by_default() {
+    let mut game = TestApp::new();
+    let [_, two] = spawn_players(&mut game);
+
+    shoot(&mut game, 0, two, Layers::ENEMY | Layers::PLAYER);
+
+    assert!(game.world().resource::<Players>().0[1].alive);
+    assert!(game.world().get_entity(two).is_some());
+}
+
+#[test]
+fn friendly_fire_hits_the_other_player_only() {
+    let mut game = TestApp::new();
+    game.world().resource_mut::<GameConfig>().friendly_fire = true;
+    let [one, two] = spawn_players(&mut game);
+
+    // never your own ship
+    shoot(&mut game, 0, one, Layers::ENEMY | Layers::PLAYER);
+    assert!(game.world().resource::<Players>().0[0].alive);
+
+    shoot(&mut game, 0, two, Layers::ENEMY | Layers::PLAYER);
+    let players = &game.world().resource::<Players>().0;
+    assert!(players[0].alive);
+    assert!(!players[1].alive);
+    assert_eq!(players[1].lives, PLAYER_LIVES - 1);
+}
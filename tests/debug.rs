@@ -0,0 +1,33 @@
+mod common;
+
+use bevy::prelude::*;
+use common::TestApp;
+use space_invaders::debug::{DebugOverlay, DebugPanel, DebugShape};
+
+#[test]
+fn f3_toggles_the_overlay() {
+    let mut game = TestApp::new();
+    // let an enemy with a hitbox and formation show up
+    game.step(70);
+    assert_eq!(game.count::<With<DebugShape>>(), 0);
+
+    game.press(KeyCode::F3);
+    game.tick();
+    game.release(KeyCode::F3);
+    game.tick();
+
+    assert!(game.world().resource::<DebugOverlay>().enabled);
+    assert_eq!(game.count::<With<DebugPanel>>(), 1);
+    assert!(game.count::<With<DebugShape>>() > 0);
+    let panel = game.entities::<With<DebugPanel>>()[0];
+    let text = game.world().get::<Text>(panel).unwrap();
+    assert!(text.sections[0].value.contains("Enemies: "));
+
+    game.press(KeyCode::F3);
+    game.tick();
+    game.tick();
+
+    assert!(!game.world().resource::<DebugOverlay>().enabled);
+    assert_eq!(game.count::<With<DebugPanel>>(), 0);
+    assert_eq!(game.count::<With<DebugShape>>(), 0);
+}
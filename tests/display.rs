@@ -0,0 +1,115 @@
+mod common;
+
+use bevy::{
+    prelude::*,
+    window::{WindowId, WindowResized},
+};
+use common::TestApp;
+use space_invaders::{
+    display::{Letterbox, LetterboxBar},
+    settings::Settings,
+    WinSize, WINDOW_HEIGHT, WINDOW_WIDTH,
+};
+
+/// Left, right, bottom and top of the world the camera shows.
+fn camera_view(game: &mut TestApp, camera: Entity) -> Vec4 {
+    let projection = game.world().get::<OrthographicProjection>(camera).unwrap();
+    Vec4::new(
+        projection.left,
+        projection.right,
+        projection.bottom,
+        projection.top,
+    ) * projection.scale
+}
+
+/// Left and right edges of the bar sprites, left to right.
+fn bar_edges(game: &mut TestApp) -> Vec<(f32, f32)> {
+    let mut query = game
+        .world()
+        .query_filtered::<(&Sprite, &Transform), With<LetterboxBar>>();
+    let mut edges: Vec<_> = query
+        .iter(game.world())
+        .map(|(sprite, transform)| {
+            let half = sprite.custom_size.unwrap().x / 2.;
+            (
+                transform.translation.x - half,
+                transform.translation.x + half,
+            )
+        })
+        .collect();
+    edges.sort_by(|a, b| a.0.total_cmp(&b.0));
+    edges
+}
+
+#[test]
+fn resized_window_keeps_the_playfield_and_letterboxes_it() {
+    let mut game = TestApp::new();
+    let camera = game
+        .world()
+        .spawn()
+        .insert_bundle(Camera2dBundle::default())
+        .id();
+    game.tick();
+    assert_eq!(game.count::<With<LetterboxBar>>(), 4);
+    let (w, h) = (WINDOW_WIDTH as f32 / 2., WINDOW_HEIGHT as f32 / 2.);
+    assert_eq!(camera_view(&mut game, camera), Vec4::new(-w, w, -h, h));
+
+    game.world().send_event(WindowResized {
+        id: WindowId::primary(),
+        width: 1920.,
+        height: 1080.,
+    });
+    game.tick();
+
+    let letterbox = *game.world().resource::<Letterbox>();
+    assert_eq!(letterbox.window, Vec2::new(1920., 1080.));
+    assert_eq!(letterbox.scale, 1080. / WINDOW_HEIGHT as f32);
+
+    // spawn positions and bounds don't move with the window
+    let win_size = game.world().resource::<WinSize>();
+    assert_eq!(
+        (win_size.w, win_size.h),
+        (WINDOW_WIDTH as f32, WINDOW_HEIGHT as f32)
+    );
+    assert_eq!(win_size.window, Vec2::new(1920., 1080.));
+
+    // minimizing the window changes nothing
+    game.world().send_event(WindowResized {
+        id: WindowId::primary(),
+        width: 0.,
+        height: 0.,
+    });
+    game.tick();
+    assert_eq!(*game.world().resource::<Letterbox>(), letterbox);
+    assert_eq!(
+        game.world().resource::<WinSize>().window,
+        Vec2::new(1920., 1080.)
+    );
+
+    // the camera shows all of the playfield's height and more of the sides,
+    // which the side bars cover up to its edges
+    let view = camera_view(&mut game, camera);
+    assert!((view.z + h).abs() < 0.01 && (view.w - h).abs() < 0.01);
+    let edges = bar_edges(&mut game);
+    assert_eq!(edges.len(), 4);
+    assert!(edges[0].0 < view.x);
+    assert_eq!(edges[0].1, -w);
+    assert_eq!(edges[3].0, w);
+    assert!(edges[3].1 > view.y);
+}
+
+#[test]
+fn function_keys_toggle_fullscreen_and_borderless() {
+    let mut game = TestApp::new();
+    game.tick();
+
+    game.press(KeyCode::F11);
+    game.tick();
+    game.release(KeyCode::F11);
+    game.press(KeyCode::F10);
+    game.tick();
+
+    let display = &game.world().resource::<Settings>().display;
+    assert!(display.fullscreen);
+    assert!(display.borderless);
+}
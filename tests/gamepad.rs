@@ -0,0 +1,105 @@
+mod common;
+
+use bevy::{input::gamepad::GamepadEventType, prelude::*};
+use common::TestApp;
+use space_invaders::{
+    components::{Laser, Player, Velocity},
+    gamepad::PlayerGamepads,
+    Paused, Players, TimeScale,
+};
+
+fn spawn_player_with_gamepad(game: &mut TestApp) -> Entity {
+    game.gamepad(0, GamepadEventType::Connected);
+    game.step_until(120, |world| world.resource::<Players>().0[0].alive);
+    game.entities::<With<Player>>()[0]
+}
+
+#[test]
+fn hot_plugging_gives_each_player_a_gamepad() {
+    let mut game = TestApp::new();
+    let slots = |game: &mut TestApp| game.world().resource::<PlayerGamepads>().0;
+
+    game.gamepad(3, GamepadEventType::Connected);
+    game.tick();
+    game.gamepad(5, GamepadEventType::Connected);
+    game.tick();
+    game.gamepad(6, GamepadEventType::Connected);
+    game.tick();
+    assert_eq!(slots(&mut game), [Some(Gamepad::new(3)), Some(Gamepad::new(5))]);
+
+    // the spare pad takes over
+    game.gamepad(3, GamepadEventType::Disconnected);
+    game.tick();
+    assert_eq!(slots(&mut game), [Some(Gamepad::new(6)), Some(Gamepad::new(5))]);
+
+    game.gamepad(5, GamepadEventType::Disconnected);
+    game.tick();
+    assert_eq!(slots(&mut game), [Some(Gamepad::new(6)), None]);
+
+    game.gamepad(8, GamepadEventType::Connected);
+    game.tick();
+    assert_eq!(slots(&mut game), [Some(Gamepad::new(6)), Some(Gamepad::new(8))]);
+}
+
+#[test]
+fn stick_steers_proportionally() {
+    let mut game = TestApp::new();
+    let player = spawn_player_with_gamepad(&mut game);
+
+    game.gamepad(
+        0,
+        GamepadEventType::AxisChanged(GamepadAxisType::LeftStickX, -0.5),
+    );
+    game.step(30);
+
+    let velocity = game.world().get::<Velocity>(player).unwrap();
+    assert!((velocity.x + 0.5).abs() < 0.01, "{}", velocity.x);
+}
+
+#[test]
+fn dpad_steers_at_full_speed() {
+    let mut game = TestApp::new();
+    let player = spawn_player_with_gamepad(&mut game);
+
+    game.gamepad(
+        0,
+        GamepadEventType::ButtonChanged(GamepadButtonType::DPadRight, 1.),
+    );
+    game.step(30);
+
+    assert_eq!(game.world().get::<Velocity>(player).unwrap().x, 1.);
+}
+
+#[test]
+fn face_button_fires() {
+    let mut game = TestApp::new();
+    spawn_player_with_gamepad(&mut game);
+
+    game.gamepad(
+        0,
+        GamepadEventType::ButtonChanged(GamepadButtonType::South, 1.),
+    );
+    game.tick();
+
+    assert_eq!(game.count::<With<Laser>>(), 2);
+}
+
+#[test]
+fn start_toggles_pause() {
+    let mut game = TestApp::new();
+    spawn_player_with_gamepad(&mut game);
+    let start = GamepadEventType::ButtonChanged(GamepadButtonType::Start, 1.);
+    let release = GamepadEventType::ButtonChanged(GamepadButtonType::Start, 0.);
+
+    game.gamepad(0, start.clone());
+    game.tick();
+    assert!(game.world().resource::<Paused>().paused);
+    assert_eq!(game.world().resource::<TimeScale>().0, 0.);
+
+    game.gamepad(0, release);
+    game.tick();
+    game.gamepad(0, start);
+    game.tick();
+    assert!(!game.world().resource::<Paused>().paused);
+    assert_eq!(game.world().resource::<TimeScale>().0, 1.);
+}
@@ -0,0 +1,327 @@
+mod common;
+
+use bevy::prelude::*;
+use common::TestApp;
+use space_invaders::{
+    collision::{Collider, CollisionEvent, Layers},
+    components::{
+        Enemy, Explosion, ExplosionKind, FromEnemy, FromPlayer, Laser, Player, PreviousPosition, Shield,
+        SpriteSize, Velocity,
+    },
+    events::{EnemyDestroyed, LaserFired, PowerUpCollected, PowerUpKind, Shooter, WaveCleared},
+    EnemyCount, GameConfig, Paused, Players, TimeScale, Wave, WaveSpawned, SPRITE_SCALE, WAVE_SIZE,
+    WINDOW_HEIGHT, WINDOW_WIDTH,
+};
+
+fn spawn_player(game: &mut TestApp) -> Entity {
+    game.step_until(120, |world| world.resource::<Players>().0[0].alive);
+    game.entities::<With<Player>>()[0]
+}
+
+/// Kind and scale of each playing explosion.
+fn explosions(game: &mut TestApp) -> Vec<(ExplosionKind, f32)> {
+    let mut query = game.world().query::<(&Explosion, &Transform)>();
+    query
+        .iter(game.world())
+        .map(|(explosion, transform)| (explosion.0, transform.scale.x))
+        .collect()
+}
+
+#[test]
+fn player_spawns_after_respawn_step() {
+    let mut game = TestApp::new();
+    assert_eq!(game.count::<With<Player>>(), 0);
+
+    spawn_player(&mut game);
+
+    assert_eq!(game.count::<With<Player>>(), 1);
+}
+
+#[test]
+fn nothing_spawns_while_paused() {
+    let mut game = TestApp::new();
+    game.world().resource_scope(|world, mut paused: Mut<Paused>| {
+        paused.toggle(&mut world.resource_mut::<TimeScale>());
+    });
+
+    game.step(180);
+    assert_eq!(game.count::<With<Player>>(), 0);
+    assert_eq!(game.count::<With<Enemy>>(), 0);
+}
+
+#[test]
+fn pressing_space_spawns_two_player_lasers() {
+    let mut game = TestApp::new();
+    spawn_player(&mut game);
+    assert_eq!(game.count::<(With<Laser>, With<FromPlayer>)>(), 0);
+
+    game.press(KeyCode::Space);
+    game.tick();
+
+    assert_eq!(game.count::<(With<Laser>, With<FromPlayer>)>(), 2);
+
+    // holding space does not fire again
+    game.tick();
+    assert_eq!(game.count::<(With<Laser>, With<FromPlayer>)>(), 2);
+}
+
+#[test]
+fn arrow_keys_move_the_player() {
+    let mut game = TestApp::new();
+    let player = spawn_player(&mut game);
+    let start_x = game.world().get::<Transform>(player).unwrap().translation.x;
+
+    game.press(KeyCode::Left);
+    game.step(10);
+
+    let x = game.world().get::<Transform>(player).unwrap().translation.x;
+    assert!(x < start_x);
+}
+
+#[test]
+fn player_stays_inside_the_window() {
+    let mut game = TestApp::new();
+    let player = spawn_player(&mut game);
+
+    game.press(KeyCode::Right);
+    game.step(120);
+
+    let x = game.world().get::<Transform>(player).unwrap().translation.x;
+    let half_width = game.textures().players[0].size().x / 2. * SPRITE_SCALE;
+    assert_eq!(x, WINDOW_WIDTH as f32 / 2. - half_width);
+    assert_eq!(game.world().get::<Velocity>(player).unwrap().x, 0.);
+}
+
+#[test]
+fn vertical_mode_moves_within_the_bottom_third() {
+    let mut game = TestApp::new();
+    let player = spawn_player(&mut game);
+    let start_y = game.world().get::<Transform>(player).unwrap().translation.y;
+
+    game.press(KeyCode::Up);
+    game.step(10);
+    assert_eq!(game.world().get::<Transform>(player).unwrap().translation.y, start_y);
+
+    game.world().resource_mut::<GameConfig>().player_vertical = true;
+    game.press(KeyCode::Up);
+    game.step(120);
+
+    let y = game.world().get::<Transform>(player).unwrap().translation.y;
+    assert!(y > start_y);
+    assert!(y < -(WINDOW_HEIGHT as f32) / 2. + WINDOW_HEIGHT as f32 / 3.);
+}
+
+#[test]
+fn enemy_laser_overlapping_player_shoots_player() {
+    let mut game = TestApp::new();
+    let player = spawn_player(&mut game);
+    let player_tf = *game.world().get::<Transform>(player).unwrap();
+
+    let laser = game.textures().enemy_laser;
+    game.world()
+        .spawn()
+        .insert(Laser)
+        .insert(FromEnemy)
+        .insert(SpriteSize(laser.size()))
+        .insert(Collider::new(Layers::ENEMY_LASER, Layers::PLAYER))
+        .insert(Transform {
+            translation: player_tf.translation,
+            scale: Vec3::new(SPRITE_SCALE, SPRITE_SCALE, 1.),
+            ..Default::default()
+        });
+    game.tick();
+
+    let player_state = &game.world().resource::<Players>().0[0];
+    assert!(!player_state.alive);
+    assert!(player_state.last_shot >= 0.);
+    assert_eq!(game.count::<With<Player>>(), 0);
+    assert_eq!(game.count::<With<Laser>>(), 0);
+
+    game.tick();
+    assert_eq!(explosions(&mut game), vec![(ExplosionKind::Fireball, 1.5)]);
+}
+
+#[test]
+fn shield_takes_one_hit_for_the_player() {
+    let mut game = TestApp::new();
+    let player = spawn_player(&mut game);
+    let player_tf = *game.world().get::<Transform>(player).unwrap();
+    game.world().send_event(PowerUpCollected {
+        player,
+        kind: PowerUpKind::Shield,
+        position: player_tf.translation,
+    });
+    game.tick();
+    assert!(game.world().get::<Shield>(player).is_some());
+
+    let laser = game.textures().enemy_laser;
+    for _ in 0..2 {
+        game.world()
+            .spawn()
+            .insert(Laser)
+            .insert(FromEnemy)
+            .insert(SpriteSize(laser.size()))
+            .insert(Collider::new(Layers::ENEMY_LASER, Layers::PLAYER))
+            .insert(Transform {
+                translation: player_tf.translation,
+                scale: Vec3::new(SPRITE_SCALE, SPRITE_SCALE, 1.),
+                ..Default::default()
+            });
+    }
+    game.tick();
+
+    // the shield went down with the first laser, the second got through
+    assert!(!game.world().resource::<Players>().0[0].alive);
+    assert_eq!(game.count::<With<Laser>>(), 0);
+}
+
+#[test]
+fn firing_sends_laser_fired_events() {
+    let mut game = TestApp::new();
+    spawn_player(&mut game);
+
+    game.press(KeyCode::Space);
+    game.tick();
+
+    let events = game.world().resource::<Events<LaserFired>>();
+    let fired: Vec<_> = events.get_reader().iter(events).copied().collect();
+    assert_eq!(fired.len(), 2);
+    assert!(fired.iter().all(|event| event.shooter == Shooter::Player));
+}
+
+#[test]
+fn player_laser_destroying_last_enemy_clears_wave() {
+    let mut game = TestApp::new();
+    game.world().resource_mut::<GameConfig>().wave_size = 1;
+    game.step_until(120, |world| world.resource::<EnemyCount>().0 > 0);
+    let enemy = game.entities::<With<Enemy>>()[0];
+    let enemy_tf = *game.world().get::<Transform>(enemy).unwrap();
+
+    let laser = game.textures().player_laser;
+    game.world()
+        .spawn()
+        .insert(Laser)
+        .insert(FromPlayer(0))
+        .insert(SpriteSize(laser.size()))
+        .insert(Collider::new(Layers::PLAYER_LASER, Layers::ENEMY))
+        .insert(enemy_tf);
+    game.tick();
+
+    assert!(game.world().get_entity(enemy).is_none());
+    assert_eq!(game.world().resource::<EnemyCount>().0, 0);
+    let events = game.world().resource::<Events<WaveCleared>>();
+    assert_eq!(events.get_reader().iter(events).count(), 1);
+    assert_eq!(game.world().resource::<Wave>().0, 2);
+    assert_eq!(game.world().resource::<WaveSpawned>().0, 0);
+
+    // the explosion subscriber picked up the kill, the last one gets the big blast
+    game.tick();
+    assert_eq!(game.count::<With<Explosion>>(), 1);
+    assert_eq!(explosions(&mut game), vec![(ExplosionKind::Blast, 2.)]);
+}
+
+#[test]
+fn killing_the_enemies_on_screen_mid_wave_does_not_clear_it() {
+    let mut game = TestApp::new();
+    game.step_until(300, |world| world.resource::<EnemyCount>().0 == 2);
+
+    for enemy in game.entities::<With<Enemy>>() {
+        game.world().despawn(enemy);
+        game.world().send_event(EnemyDestroyed {
+            enemy,
+            position: Vec3::ZERO,
+            by_player: None,
+        });
+    }
+    game.tick();
+
+    assert_eq!(game.world().resource::<EnemyCount>().0, 0);
+    let events = game.world().resource::<Events<WaveCleared>>();
+    assert_eq!(events.get_reader().iter(events).count(), 0);
+    assert_eq!(game.world().resource::<Wave>().0, 1);
+
+    // just bursts, the blast is kept for the end of the wave
+    game.tick();
+    assert_eq!(
+        explosions(&mut game),
+        vec![(ExplosionKind::Burst, 1.), (ExplosionKind::Burst, 1.)]
+    );
+}
+
+#[test]
+fn only_the_last_of_several_kills_in_a_frame_blasts() {
+    let mut game = TestApp::new();
+    game.world().resource_mut::<GameConfig>().enemy_max = 0;
+    // the whole wave is out, these are its last
+    game.world().resource_mut::<WaveSpawned>().0 = WAVE_SIZE;
+    game.tick();
+
+    for x in [-100., 100.] {
+        let enemy = game.world().spawn().id();
+        game.world().send_event(EnemyDestroyed {
+            enemy,
+            position: Vec3::new(x, 0., 0.),
+            by_player: None,
+        });
+    }
+    game.step(2);
+
+    let mut kinds: Vec<_> = explosions(&mut game)
+        .into_iter()
+        .map(|(kind, _)| kind)
+        .collect();
+    kinds.sort_by_key(|kind| *kind == ExplosionKind::Blast);
+    assert_eq!(kinds, vec![ExplosionKind::Burst, ExplosionKind::Blast]);
+}
+
+#[test]
+fn colliders_only_meet_layers_in_their_mask() {
+    let mut game = TestApp::new();
+    let mut spawn = |layer, mask| {
+        game.world()
+            .spawn()
+            .insert(SpriteSize::from((10., 10.)))
+            .insert(Collider::new(layer, mask))
+            .insert(Transform::default())
+            .id()
+    };
+    let meteor = spawn(Layers::METEOR, Layers::PLAYER_LASER);
+    let laser = spawn(Layers::PLAYER_LASER, Layers::NONE);
+    spawn(Layers::POWER_UP, Layers::PLAYER);
+
+    game.tick();
+
+    let events = game.world().resource::<Events<CollisionEvent>>();
+    let pairs: Vec<_> = events
+        .get_reader()
+        .iter(events)
+        .map(|CollisionEvent(a, b)| (*a, *b))
+        .collect();
+    assert_eq!(pairs.len(), 1);
+    assert!(pairs[0] == (meteor, laser) || pairs[0] == (laser, meteor));
+}
+
+#[test]
+fn fast_enemy_laser_does_not_tunnel_through_player() {
+    let mut game = TestApp::new();
+    let player = spawn_player(&mut game);
+    let player_tf = *game.world().get::<Transform>(player).unwrap();
+
+    // moved from well above to well below the player in a single tick
+    let laser = game.textures().enemy_laser;
+    game.world()
+        .spawn()
+        .insert(Laser)
+        .insert(FromEnemy)
+        .insert(SpriteSize(laser.size()))
+        .insert(Collider::new(Layers::ENEMY_LASER, Layers::PLAYER))
+        .insert(PreviousPosition(player_tf.translation + Vec3::new(0., 100., 0.)))
+        .insert(Transform {
+            translation: player_tf.translation - Vec3::new(0., 100., 0.),
+            scale: Vec3::new(SPRITE_SCALE, SPRITE_SCALE, 1.),
+            ..Default::default()
+        });
+    game.tick();
+
+    assert!(!game.world().resource::<Players>().0[0].alive);
+}
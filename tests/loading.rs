@@ -0,0 +1,74 @@
+use std::{thread, time::Duration};
+
+use bevy::{asset::AssetPlugin, prelude::*, render::texture::ImageTextureLoader, text::FontLoader};
+use space_invaders::{
+    atlas::SpriteSheetPlugin,
+    loading::{Loading, LoadingPlugin},
+    GameTextures, Screen, TextureHandles, SPRITE_SHEET,
+};
+
+/// The loading screen with a real asset server on the `assets` folder, but
+/// nothing else of the game.
+fn loading_app() -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(AssetPlugin)
+        .add_asset::<Image>()
+        .add_asset::<TextureAtlas>()
+        .add_asset::<Font>()
+        .init_asset_loader::<ImageTextureLoader>()
+        .init_asset_loader::<FontLoader>()
+        .insert_resource(Screen::Loading)
+        .add_plugin(SpriteSheetPlugin)
+        .add_plugin(LoadingPlugin);
+
+    let sheet = app.world.resource::<AssetServer>().load(SPRITE_SHEET);
+    app.world.resource_mut::<Loading>().add(&sheet);
+    app.insert_resource(TextureHandles {
+        sheet,
+        explosions: Default::default(),
+    });
+    app
+}
+
+/// Update until `done`, giving the asset server's threads time to work.
+fn update_until(app: &mut App, mut done: impl FnMut(&World) -> bool) {
+    for _ in 0..2000 {
+        app.update();
+        if done(&app.world) {
+            return;
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+    panic!("still loading");
+}
+
+#[test]
+fn title_follows_once_everything_has_loaded() {
+    let mut app = loading_app();
+
+    update_until(&mut app, |world| {
+        *world.resource::<Screen>() != Screen::Loading
+    });
+
+    assert_eq!(*app.world.resource::<Screen>(), Screen::Title);
+    assert_eq!(app.world.resource::<Loading>().progress(), 1.);
+    let game_textures = app.world.resource::<GameTextures>();
+    assert_eq!(game_textures.enemy.size(), Vec2::new(93., 84.));
+}
+
+#[test]
+fn a_missing_file_is_named_on_the_error_screen() {
+    let mut app = loading_app();
+    let missing: Handle<Image> = app.world.resource::<AssetServer>().load("PNG/nothing.png");
+    app.world.resource_mut::<Loading>().add(&missing);
+
+    update_until(&mut app, |world| {
+        world.resource::<Loading>().error.is_some()
+    });
+    app.update();
+
+    let error = app.world.resource::<Loading>().error.clone().unwrap();
+    assert!(error.contains("PNG/nothing.png"), "{error}");
+    assert_eq!(*app.world.resource::<Screen>(), Screen::Loading);
+}
@@ -0,0 +1,146 @@
+use std::{net::UdpSocket, thread, time::Duration};
+
+use bevy::math::Vec2;
+use space_invaders::{
+    controls::{Action, ActionFrame, ActionSet},
+    netcode::{LinkShim, RollbackSession, SessionConfig, Simulation, UdpTransport},
+    MAX_PLAYERS,
+};
+
+const TICKS: usize = 300;
+
+/// Two ships that move and fire, keeping every tick's positions and shot
+/// counts so peers can be compared tick by tick.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Duel {
+    ships: [Vec2; MAX_PLAYERS],
+    shots: [u32; MAX_PLAYERS],
+    previous: [ActionSet; MAX_PLAYERS],
+    history: Vec<([Vec2; MAX_PLAYERS], [u32; MAX_PLAYERS])>,
+}
+
+impl Simulation for Duel {
+    type State = Duel;
+
+    fn save(&self) -> Duel {
+        self.clone()
+    }
+
+    fn load(&mut self, state: &Duel) {
+        *self = state.clone();
+    }
+
+    fn advance(&mut self, inputs: [ActionFrame; MAX_PLAYERS]) {
+        for (number, input) in inputs.into_iter().enumerate() {
+            let axis = |negative, positive| {
+                input.held.contains(positive) as i32 as f32
+                    - input.held.contains(negative) as i32 as f32
+            };
+            self.ships[number] += Vec2::new(
+                axis(Action::MoveLeft, Action::MoveRight),
+                axis(Action::MoveDown, Action::MoveUp),
+            ) + input.stick;
+
+            if input.held.contains(Action::Fire) && !self.previous[number].contains(Action::Fire) {
+                self.shots[number] += 1;
+            }
+            self.previous[number] = input.held;
+        }
+        self.history.push((self.ships, self.shots));
+    }
+}
+
+/// Scripted input of player `number` on `tick`, changing often enough that
+/// predictions keep failing.
+fn script(number: usize, tick: u32) -> ActionFrame {
+    let held: &[Action] = match (tick / (7 + number as u32 * 4)) % 4 {
+        0 => &[Action::MoveLeft],
+        1 => &[Action::Fire],
+        2 => &[Action::MoveRight, Action::MoveUp],
+        _ => &[],
+    };
+    ActionFrame {
+        held: held.iter().copied().collect(),
+        stick: Vec2::new(0., (tick % 5) as f32 * 0.1),
+    }
+}
+
+type Peer = RollbackSession<LinkShim<UdpTransport>, Duel>;
+
+fn peers(latency: Duration, loss: f64) -> [Peer; 2] {
+    let a = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let b = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let addrs = [a.local_addr().unwrap(), b.local_addr().unwrap()];
+    drop((a, b));
+
+    [0, 1].map(|number| {
+        let transport = UdpTransport::bind(addrs[number], addrs[1 - number]).unwrap();
+        let link = LinkShim::new(transport, latency, loss, number as u64).with_jitter(latency / 2);
+        let config = SessionConfig {
+            local_player: number,
+            ..Default::default()
+        };
+        RollbackSession::new(config, link)
+    })
+}
+
+/// Run both peers, a frame each per millisecond, until both have confirmed
+/// `TICKS` ticks.
+fn play(peers: &mut [Peer; 2], sims: &mut [Duel; 2]) {
+    for _ in 0..TICKS * 20 {
+        if peers
+            .iter()
+            .all(|peer| peer.confirmed_tick() as usize >= TICKS)
+        {
+            return;
+        }
+        for (number, (peer, sim)) in peers.iter_mut().zip(sims.iter_mut()).enumerate() {
+            let input = script(number, peer.tick());
+            peer.advance_frame(sim, input);
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+    panic!("peers did not confirm {TICKS} ticks");
+}
+
+/// The same game played on one machine with every input known up front.
+fn reference() -> Duel {
+    let mut sim = Duel::default();
+    let delay = SessionConfig::default().input_delay;
+    for tick in 0..TICKS as u32 {
+        let input = |number| match tick.checked_sub(delay) {
+            Some(tick) => script(number, tick),
+            None => ActionFrame::default(),
+        };
+        sim.advance([input(0), input(1)]);
+    }
+    sim
+}
+
+#[test]
+fn peers_stay_in_sync_over_a_bad_link() {
+    let mut peers = peers(Duration::from_millis(6), 0.2);
+    let mut sims = [Duel::default(), Duel::default()];
+
+    play(&mut peers, &mut sims);
+
+    let expected = reference().history;
+    for (peer, sim) in peers.iter().zip(sims.iter()) {
+        assert!(peer.stats().rollbacks > 0);
+        assert_eq!(sim.history[..TICKS], expected[..]);
+    }
+}
+
+#[test]
+fn a_silent_peer_stalls_the_session() {
+    let [mut peer, _] = peers(Duration::ZERO, 1.);
+    let mut sim = Duel::default();
+
+    for tick in 0..20 {
+        peer.advance_frame(&mut sim, script(0, tick));
+    }
+
+    let max_prediction = SessionConfig::default().max_prediction;
+    assert_eq!(peer.tick(), max_prediction);
+    assert_eq!(peer.stats().stalls, 20 - max_prediction);
+}
@@ -0,0 +1,186 @@
+#![allow(clippy::type_complexity, clippy::too_many_arguments)]
+
+mod common;
+
+use std::{collections::BTreeMap, net::UdpSocket, thread, time::Duration};
+
+use bevy::prelude::*;
+use common::TestApp;
+use rand::Rng;
+use space_invaders::{
+    components::{Enemy, ExplosionToSpawn, Laser, Player},
+    netcode::{self, LinkShim, Netplay, Online, RollbackSession, SessionConfig, UdpTransport},
+    sound::PlaySfx,
+    EnemyCount, GameConfig, GameRng, Players, Wave, MAX_PLAYERS,
+};
+
+const TICKS: usize = 300;
+
+type Link = LinkShim<UdpTransport>;
+
+/// What both peers must agree on after a tick.
+#[derive(Debug, PartialEq)]
+struct Summary {
+    /// alive, lives and score of each player
+    players: Vec<(bool, u32, u32)>,
+    enemies: u32,
+    lasers: usize,
+    wave: u32,
+    next_random: u64,
+    /// positions of the players, enemies and lasers, bit for bit
+    bodies: Vec<[u32; 2]>,
+}
+
+/// Resource - summary of every tick, kept out of the rollbacks
+#[derive(Default)]
+struct History(Vec<Summary>);
+
+fn record_system(
+    online: Res<Online>,
+    players: Res<Players>,
+    enemy_count: Res<EnemyCount>,
+    wave: Res<Wave>,
+    rng: Res<GameRng>,
+    mut history: ResMut<History>,
+    laser_query: Query<(), With<Laser>>,
+    body_query: Query<&Transform, Or<(With<Player>, With<Enemy>, With<Laser>)>>,
+) {
+    let mut bodies: Vec<_> = body_query
+        .iter()
+        .map(|tf| [tf.translation.x.to_bits(), tf.translation.y.to_bits()])
+        .collect();
+    bodies.sort_unstable();
+
+    // a replayed tick takes the place of the one it replays
+    history.0.truncate(online.tick as usize);
+    history.0.push(Summary {
+        players: players
+            .0
+            .iter()
+            .map(|player| (player.alive, player.lives, player.score))
+            .collect(),
+        enemies: enemy_count.0,
+        lasers: laser_query.iter().count(),
+        wave: wave.0,
+        next_random: rng.rng.clone().gen(),
+        bodies,
+    });
+}
+
+/// Resource - runs of each tick that played sounds or spawned explosions,
+/// and how many of each there were
+#[derive(Default)]
+struct Effects {
+    runs: BTreeMap<u32, u32>,
+    sounds: usize,
+    explosions: usize,
+}
+
+fn effects_system(
+    online: Res<Online>,
+    mut effects: ResMut<Effects>,
+    mut play_sfx_events: EventReader<PlaySfx>,
+    explosion_query: Query<(), Added<ExplosionToSpawn>>,
+) {
+    let sounds = play_sfx_events.iter().count();
+    let explosions = explosion_query.iter().count();
+    if sounds + explosions > 0 {
+        *effects.runs.entry(online.tick).or_default() += 1;
+        effects.sounds += sounds;
+        effects.explosions += explosions;
+    }
+}
+
+fn peers(latency: Duration, loss: f64) -> [TestApp; 2] {
+    let a = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let b = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let addrs = [a.local_addr().unwrap(), b.local_addr().unwrap()];
+    drop((a, b));
+
+    [0, 1].map(|number| {
+        let mut game = TestApp::new();
+        game.world().insert_resource(GameConfig {
+            players: MAX_PLAYERS,
+            ..Default::default()
+        });
+        game.app
+            .init_resource::<History>()
+            .init_resource::<Effects>()
+            .add_system_to_stage(CoreStage::Last, record_system)
+            .add_system_to_stage(CoreStage::Last, effects_system);
+
+        let transport = UdpTransport::bind(addrs[number], addrs[1 - number]).unwrap();
+        let link = LinkShim::new(transport, latency, loss, number as u64).with_jitter(latency / 2);
+        let config = SessionConfig {
+            local_player: number,
+            ..Default::default()
+        };
+        netcode::start(&mut game.app, RollbackSession::new(config, link));
+        game
+    })
+}
+
+/// Keys held by player `number` on `frame`, changing often enough that
+/// predictions keep failing.
+fn script(game: &mut TestApp, number: usize, frame: usize) {
+    for key in [KeyCode::Left, KeyCode::Right, KeyCode::Space] {
+        game.release(key);
+    }
+    match (frame / (11 + number * 6)) % 3 {
+        0 => game.press(KeyCode::Left),
+        1 => game.press(KeyCode::Right),
+        _ => {}
+    }
+    if (frame / (3 + number)).is_multiple_of(2) {
+        game.press(KeyCode::Space);
+    }
+}
+
+fn confirmed(game: &mut TestApp) -> usize {
+    game.world()
+        .resource::<Netplay<Link>>()
+        .session()
+        .confirmed_tick() as usize
+}
+
+#[test]
+fn peers_play_the_same_game_over_a_lossy_link() {
+    let mut games = peers(Duration::from_millis(20), 0.1);
+
+    for frame in 0..TICKS * 20 {
+        if games.iter_mut().all(|game| confirmed(game) >= TICKS) {
+            break;
+        }
+        for (number, game) in games.iter_mut().enumerate() {
+            script(game, number, frame);
+            game.tick();
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    for game in games.iter_mut() {
+        let session = game.world().resource::<Netplay<Link>>().session();
+        assert!(session.confirmed_tick() as usize >= TICKS, "peers stalled");
+        assert!(session.stats().rollbacks > 0, "nothing was predicted wrong");
+
+        // replayed ticks leave the effects to their first run
+        let effects = game.world().resource::<Effects>();
+        assert!(effects.sounds > 0 && effects.explosions > 0);
+        for (tick, runs) in effects.runs.iter() {
+            assert_eq!(*runs, 1, "effects of tick {tick} played {runs} times");
+        }
+    }
+
+    let [host, guest] = &mut games;
+    let host = &host.world().resource::<History>().0[..TICKS];
+    let guest = &guest.world().resource::<History>().0[..TICKS];
+    for (tick, (host, guest)) in host.iter().zip(guest).enumerate() {
+        assert_eq!(host, guest, "peers went apart on tick {tick}");
+    }
+
+    assert!(host.iter().any(|summary| summary.enemies > 0));
+    assert!(host.iter().any(|summary| summary.lasers > 0));
+    assert!(host
+        .iter()
+        .any(|summary| summary.players.iter().all(|player| player.0)));
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use bevy::prelude::*;
+use common::TestApp;
+use space_invaders::{
+    collision::{Collider, Layers},
+    components::{Enemy, FromPlayer, Laser, SpriteSize},
+    particles::{Particle, ParticleEffect, ParticleEmitter, MAX_PARTICLES},
+    settings::Settings,
+    EnemyCount,
+};
+
+fn particle_positions(game: &mut TestApp) -> Vec<Vec3> {
+    let mut query = game.world().query_filtered::<&Transform, With<Particle>>();
+    query
+        .iter(game.world())
+        .map(|transform| transform.translation)
+        .collect()
+}
+
+#[test]
+fn destroyed_enemy_leaves_sparks_and_debris() {
+    let mut game = TestApp::new();
+    // it's the only enemy, the hit-stop would hold the sparks back
+    game.world().resource_mut::<Settings>().effects.hit_stop = false;
+    game.step_until(120, |world| world.resource::<EnemyCount>().0 > 0);
+    let enemy = game.entities::<With<Enemy>>()[0];
+    let enemy_tf = *game.world().get::<Transform>(enemy).unwrap();
+
+    let laser = game.textures().player_laser;
+    game.world()
+        .spawn()
+        .insert(Laser)
+        .insert(FromPlayer(0))
+        .insert(SpriteSize(laser.size()))
+        .insert(Collider::new(Layers::PLAYER_LASER, Layers::ENEMY))
+        .insert(enemy_tf);
+    game.step(2);
+
+    let near: Vec<_> = particle_positions(&mut game)
+        .into_iter()
+        .filter(|position| {
+            position
+                .truncate()
+                .distance(enemy_tf.translation.truncate())
+                < 20.
+        })
+        .collect();
+    assert!(near.len() >= 10, "{near:?}");
+
+    // the debris is gone after its 0.9 s, only engine trail is left
+    game.step(60);
+    let mut query = game.world().query::<&Particle>();
+    assert!(query
+        .iter(game.world())
+        .all(|particle| particle.lifetime < 0.5));
+}
+
+#[test]
+fn particle_count_is_capped() {
+    let mut game = TestApp::new();
+    game.tick();
+    let sparks = ParticleEffect::sparks(game.textures());
+
+    for _ in 0..50 {
+        game.world()
+            .spawn()
+            .insert_bundle(TransformBundle::default())
+            .insert(ParticleEmitter::burst(sparks.clone(), 20));
+    }
+    game.tick();
+
+    assert_eq!(game.count::<With<Particle>>(), MAX_PARTICLES);
+    assert_eq!(game.count::<With<ParticleEmitter>>(), 0);
+}
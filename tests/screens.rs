@@ -0,0 +1,126 @@
+mod common;
+
+use bevy::{
+    prelude::*,
+    window::{ReceivedCharacter, WindowId},
+};
+use common::TestApp;
+use space_invaders::{
+    collision::{Collider, Layers},
+    components::{FromEnemy, Laser, Player, SpriteSize},
+    scores::HighScores,
+    screens::GameOver,
+    GameRng, Paused, Players, Screen, Wave, SPRITE_SCALE,
+};
+
+/// Play until the player is on their last life with `score`, then shoot them.
+fn lose_with_score(game: &mut TestApp, score: u32) {
+    game.step_until(120, |world| world.resource::<Players>().0[0].alive);
+    let player = game.entities::<With<Player>>()[0];
+    let player_tf = *game.world().get::<Transform>(player).unwrap();
+    {
+        let mut players = game.world().resource_mut::<Players>();
+        players.0[0].lives = 1;
+        players.0[0].score = score;
+    }
+
+    let laser = game.textures().enemy_laser;
+    game.world()
+        .spawn()
+        .insert(Laser)
+        .insert(FromEnemy)
+        .insert(SpriteSize(laser.size()))
+        .insert(Collider::new(Layers::ENEMY_LASER, Layers::PLAYER))
+        .insert(Transform {
+            translation: player_tf.translation,
+            scale: Vec3::new(SPRITE_SCALE, SPRITE_SCALE, 1.),
+            ..Default::default()
+        });
+    game.step(2);
+}
+
+fn type_text(game: &mut TestApp, text: &str) {
+    for char in text.chars() {
+        game.world().send_event(ReceivedCharacter {
+            id: WindowId::primary(),
+            char,
+        });
+    }
+}
+
+#[test]
+fn losing_the_last_life_ends_the_game() {
+    let mut game = TestApp::new();
+
+    lose_with_score(&mut game, 0);
+
+    assert_eq!(*game.world().resource::<Screen>(), Screen::GameOver);
+    assert!(game.world().resource::<Paused>().paused);
+    // nothing to enter for a score of zero
+    assert!(game.world().resource::<GameOver>().pending.is_empty());
+}
+
+#[test]
+fn new_high_score_asks_for_initials() {
+    let mut game = TestApp::new();
+    game.world().resource_mut::<Wave>().0 = 4;
+    let seed = game.world().resource::<GameRng>().seed;
+
+    lose_with_score(&mut game, 1500);
+    assert_eq!(game.world().resource::<GameOver>().pending, vec![0]);
+
+    type_text(&mut game, "j-d?xyz");
+    game.tick();
+    assert_eq!(game.world().resource::<GameOver>().initials, "JDX");
+
+    game.press(KeyCode::Back);
+    game.tick();
+    game.release(KeyCode::Back);
+    game.press(KeyCode::Return);
+    game.tick();
+
+    let entry = &game.world().resource::<HighScores>().entries[0];
+    assert_eq!(entry.initials, "JD");
+    assert_eq!((entry.score, entry.wave, entry.seed), (1500, 4, seed));
+    assert_eq!(entry.players, 1);
+    assert_eq!(entry.date.len(), "2024-01-01".len());
+    let game_over = game.world().resource::<GameOver>();
+    assert!(game_over.pending.is_empty());
+    assert_eq!(game_over.new_places, vec![0]);
+    // the same Enter does not skip the table
+    assert_eq!(*game.world().resource::<Screen>(), Screen::GameOver);
+}
+
+#[test]
+fn enter_on_game_over_starts_a_fresh_game() {
+    let mut game = TestApp::new();
+    lose_with_score(&mut game, 0);
+    let seed = game.world().resource::<GameRng>().seed;
+
+    game.press(KeyCode::Return);
+    game.tick();
+
+    assert_eq!(*game.world().resource::<Screen>(), Screen::Playing);
+    assert!(!game.world().resource::<Paused>().paused);
+    assert_ne!(game.world().resource::<GameRng>().seed, seed);
+    assert_eq!(game.world().resource::<Wave>().0, 1);
+    game.step_until(120, |world| world.resource::<Players>().0[0].alive);
+}
+
+#[test]
+fn title_waits_for_a_start() {
+    let mut game = TestApp::new();
+    game.world().insert_resource(Screen::Title);
+
+    game.step(30);
+    assert!(game.world().resource::<Paused>().paused);
+    assert_eq!(*game.world().resource::<Screen>(), Screen::Title);
+
+    // fire starts the game, without firing
+    game.press(KeyCode::Space);
+    game.step(2);
+
+    assert_eq!(*game.world().resource::<Screen>(), Screen::Playing);
+    assert!(!game.world().resource::<Paused>().paused);
+    assert_eq!(game.count::<With<Laser>>(), 0);
+}
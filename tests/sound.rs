@@ -0,0 +1,101 @@
+mod common;
+
+use bevy::prelude::*;
+use common::TestApp;
+use space_invaders::{
+    components::{Player, Shield},
+    events::{PowerUpCollected, PowerUpKind},
+    settings::Settings,
+    sound::{PlaySfx, Sfx, SoundPlayer, SFX_MAX_VOICES},
+    Players, SHIELD_DURATION, TIME_STEP,
+};
+
+fn spawn_player(game: &mut TestApp) -> Entity {
+    game.step_until(120, |world| world.resource::<Players>().0[0].alive);
+    game.entities::<With<Player>>()[0]
+}
+
+fn tap(game: &mut TestApp, key: KeyCode) {
+    game.press(key);
+    game.tick();
+    game.release(key);
+    game.tick();
+}
+
+fn played(world: &World, sfx: Sfx) -> bool {
+    let events = world.resource::<Events<PlaySfx>>();
+    events
+        .get_reader()
+        .iter(events)
+        .any(|event| *event == PlaySfx(sfx))
+}
+
+fn player_laser_voices(game: &mut TestApp) -> usize {
+    game.world()
+        .resource::<SoundPlayer>()
+        .voices(Sfx::PlayerLaser)
+}
+
+#[test]
+fn firing_plays_the_player_laser_sound() {
+    let mut game = TestApp::new();
+    spawn_player(&mut game);
+
+    game.press(KeyCode::Space);
+    game.tick();
+
+    let events = game.world().resource::<Events<PlaySfx>>();
+    let played = events
+        .get_reader()
+        .iter(events)
+        .filter(|event| **event == PlaySfx(Sfx::PlayerLaser))
+        .count();
+    assert_eq!(played, 2);
+    assert_eq!(player_laser_voices(&mut game), 2);
+}
+
+#[test]
+fn shield_sounds_going_up_and_wearing_off() {
+    let mut game = TestApp::new();
+    let player = spawn_player(&mut game);
+    game.world().send_event(PowerUpCollected {
+        player,
+        kind: PowerUpKind::Shield,
+        position: Vec3::ZERO,
+    });
+    game.tick();
+    assert!(played(game.world(), Sfx::ShieldUp));
+
+    let ticks = (SHIELD_DURATION / TIME_STEP as f64) as usize + 10;
+    game.step_until(ticks, |world| played(world, Sfx::ShieldDown));
+    assert!(game.world().get::<Shield>(player).is_none());
+}
+
+#[test]
+fn copies_of_one_sound_are_capped() {
+    let mut game = TestApp::new();
+    spawn_player(&mut game);
+
+    // two lasers a shot, quicker than the sound ends
+    for _ in 0..3 {
+        tap(&mut game, KeyCode::Space);
+    }
+
+    assert_eq!(player_laser_voices(&mut game), SFX_MAX_VOICES);
+}
+
+#[test]
+fn m_mutes_the_game() {
+    let mut game = TestApp::new();
+    spawn_player(&mut game);
+
+    tap(&mut game, KeyCode::M);
+    assert!(game.world().resource::<Settings>().audio.muted);
+    tap(&mut game, KeyCode::Space);
+    assert_eq!(player_laser_voices(&mut game), 0);
+
+    tap(&mut game, KeyCode::M);
+    assert!(!game.world().resource::<Settings>().audio.muted);
+    tap(&mut game, KeyCode::Space);
+    assert_eq!(player_laser_voices(&mut game), 2);
+}